@@ -0,0 +1,81 @@
+use std::env::var_os;
+
+use std::path::PathBuf;
+
+use std::sync::OnceLock;
+
+use thiserror::Error;
+
+
+
+#[derive(Error, Debug)]
+#[allow(clippy::enum_variant_names)]
+pub enum FoldersError {
+    #[error("HOME is not set, so ATA can't know where to keep its files (set HOME, or {0})")]
+    MissingHomeError(&'static str),
+}
+impl FoldersError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            FoldersError::MissingHomeError(_) => "home_not_set",
+        }
+    }
+}
+
+// Folders ATA keeps its files in, following the XDG base directory specification
+pub struct AtaFolders {
+    pub config: PathBuf,        // data.json                                   ($XDG_CONFIG_HOME/ATA, ~/.config/ATA)
+    pub data: PathBuf,          // Mod store, vanilla backups, install script  ($XDG_DATA_HOME/ATA, ~/.local/share/ATA)
+    pub cache: PathBuf,         // Staging folders of the mods being installed ($XDG_CACHE_HOME/ATA, ~/.cache/ATA)
+    pub state: PathBuf,         // Log and copies of data.json                 ($XDG_STATE_HOME/ATA, ~/.local/state/ATA)
+    pub user_data: PathBuf,     // Where other programs keep their data too, Steam included ($XDG_DATA_HOME, ~/.local/share)
+}
+
+static ATA_FOLDERS: OnceLock<AtaFolders> = OnceLock::new();
+
+// Finds the folders from the environment, has to be called once when ATA starts, before using them
+pub fn init_ata_folders() -> Result<&'static AtaFolders, FoldersError> {
+    let user_data = xdg_folder("XDG_DATA_HOME", ".local/share")?;
+
+    let ata_folders = AtaFolders {
+        config: xdg_folder("XDG_CONFIG_HOME", ".config")?.join("ATA"),
+        data: user_data.join("ATA"),
+        cache: xdg_folder("XDG_CACHE_HOME", ".cache")?.join("ATA"),
+        state: xdg_folder("XDG_STATE_HOME", ".local/state")?.join("ATA"),
+        user_data,
+    };
+
+    Ok(ATA_FOLDERS.get_or_init(|| ata_folders))
+}
+
+// Tests keep everything in a folder of their own inside the temporary folder, one for each run
+#[cfg(test)]
+pub fn init_test_ata_folders() -> &'static AtaFolders {
+    ATA_FOLDERS.get_or_init(|| {
+        let test_folder = std::env::temp_dir().join(format!("ata-tests-{}", std::process::id()));
+        AtaFolders {
+            config: test_folder.join("config"),
+            data: test_folder.join("data"),
+            cache: test_folder.join("cache"),
+            state: test_folder.join("state"),
+            user_data: test_folder.join("user_data"),
+        }
+    })
+}
+
+pub fn ata_folders() -> &'static AtaFolders {
+    ATA_FOLDERS.get().expect("ATA's folders are found when it starts")
+}
+
+// Folder given by the XDG variable, or the default one inside HOME
+// The specification says to ignore variables that are empty or not absolute paths
+fn xdg_folder(variable: &'static str, default_in_home: &str) -> Result<PathBuf, FoldersError> {
+    if let Some(folder) = var_os(variable).map(PathBuf::from) && folder.is_absolute() {
+        return Ok(folder);
+    }
+
+    match var_os("HOME").map(PathBuf::from) {
+        Some(home_folder) if home_folder.is_absolute() => Ok(home_folder.join(default_in_home)),
+        _ => Err(FoldersError::MissingHomeError(variable)),
+    }
+}
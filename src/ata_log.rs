@@ -0,0 +1,20 @@
+use std::fs::{create_dir_all, OpenOptions};
+
+use std::io::Write;
+
+use crate::ata_folders::ata_folders;
+
+use crate::data_saving::{format_timestamp, seconds_since_epoch};
+
+
+
+// Adds a line to the log (ata.log in the state folder) about something ATA changed, like a mod being installed
+// Not being able to write the log isn't a reason to stop what's being done, so errors are ignored
+pub fn log_event(message: &str) {
+    let log_path = ata_folders().state.join("ata.log");
+
+    let _ = create_dir_all(&ata_folders().state);
+    if let Ok(mut log_file) = OpenOptions::new().create(true).append(true).open(log_path) {
+        let _ = writeln!(log_file, "[{}] {}", format_timestamp(seconds_since_epoch()), message);
+    }
+}
@@ -0,0 +1,462 @@
+use std::error::Error;
+
+use std::path::PathBuf;
+
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand, ValueEnum};
+
+use serde::Serialize;
+
+use serde_json::json;
+
+use thiserror::Error;
+
+use crate::ata_log::log_event;
+
+use crate::data_saving::{data_file_path, is_broken_data_file_error, Config, Mod, ModType};
+
+use crate::deployment::{repair_game_folder, show_repair_report};
+
+use crate::features::{
+    disable_mod, enable_mod, filter_and_sort_mods, install_mod, installed_mod, list_mods, load_order, mod_info, move_mod, show_load_order,
+    show_mod_info, uninstall_mod, InstallChoices, ModFilter, ModSorting, ModifiedFiles, OrderPlace,
+};
+
+use crate::mod_conflicts::ConflictChoice;
+
+use crate::output::{json_output, print_json, OutputFormat};
+
+use crate::starting_checks::{check_for_required_modding_files, check_path};
+
+
+
+/* ---------------------------- */
+/*   FLAGS FOR QUICK FEATURES   */
+/* ---------------------------- */
+
+// Without a command ATA starts the interactive menu, with one it does just that without asking anything (for scripts)
+#[derive(Parser)]
+#[command(
+    name = "ata",
+    version,
+    about = "Accord's Timeline Alterer, NieR:Automata's mod manager for Linux"
+)]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// How to print the results of a command (json prints a single JSON value, errors included)
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Text)]
+    pub output: OutputFormat,
+}
+
+// Whether JSON output was asked for, looking at the raw arguments since they couldn't be parsed
+pub fn json_output_asked() -> bool {
+    let args: Vec<String> = std::env::args().collect();
+    args.windows(2).any(|pair| pair[0] == "--output" && pair[1] == "json") || args.iter().any(|arg| arg == "--output=json")
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Install a mod from an archive, a folder or a single mod file
+    Install {
+        mod_path: PathBuf,
+        /// Name given to the mod, used to uninstall, enable or disable it later
+        #[arg(long)]
+        name: String,
+        /// Install every file of the mod as this type, instead of the detected ones
+        #[arg(long = "type", value_enum)]
+        mod_type: Option<ModType>,
+        /// What to do with the files installed mods have too
+        #[arg(long, value_enum, default_value_t = ConflictChoice::Skip)]
+        on_conflict: ConflictChoice,
+    },
+    /// Uninstall a mod, putting back the game files it replaced
+    Uninstall {
+        /// Name or ID of the mod
+        mod_name: String,
+        /// Also remove the files that were modified after the installation (they are kept otherwise)
+        #[arg(long)]
+        remove_modified: bool,
+    },
+    /// Put the files of a disabled mod back in the game folder
+    Enable {
+        /// Name or ID of the mod
+        mod_name: String,
+    },
+    /// Remove the files of a mod from the game folder without uninstalling it
+    Disable {
+        /// Name or ID of the mod
+        mod_name: String,
+    },
+    /// List the installed mods
+    List {
+        /// Only mods with files of this type
+        #[arg(long = "type", value_enum)]
+        mod_type: Option<ModType>,
+        /// Only enabled mods
+        #[arg(long, conflicts_with = "disabled")]
+        enabled: bool,
+        /// Only disabled mods
+        #[arg(long)]
+        disabled: bool,
+        /// Only mods with this in their name
+        #[arg(long)]
+        name: Option<String>,
+        /// Order of the mods
+        #[arg(long, value_enum, default_value_t = ModSorting::Id)]
+        sort: ModSorting,
+    },
+    /// Show everything ATA knows about an installed mod
+    Info {
+        /// Name or ID of the mod
+        mod_name: String,
+    },
+    /// Show or change the load order (mods later in the order win the files they share with earlier ones)
+    Order {
+        #[command(subcommand)]
+        action: OrderAction,
+    },
+    /// Rebuild the game folder from the mod store (copies of the enabled mods that win each file, originals everywhere else)
+    Repair,
+    /// Check that the data file, the game folder and the required modding files are fine
+    Doctor,
+    /// Read or change ATA's settings
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum OrderAction {
+    /// Print the mods from the lowest to the highest priority
+    List,
+    /// Move a mod before or after another one, then put the winning copy of every shared file in the game folder
+    Move {
+        /// Name or ID of the mod to move
+        mod_name: String,
+        /// Name or ID of the mod it goes right before
+        #[arg(long, conflicts_with = "after", required_unless_present = "after")]
+        before: Option<String>,
+        /// Name or ID of the mod it goes right after
+        #[arg(long)]
+        after: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// Print the value of a setting
+    Get { key: ConfigKey },
+    /// Change the value of a setting
+    Set { key: ConfigKey, value: String },
+}
+
+// Settings that can be read and changed from the command line
+#[derive(ValueEnum, Clone, Copy)]
+pub enum ConfigKey {
+    /// Folder the game is installed in (the one containing NieRAutomata.exe)
+    GamePath,
+}
+
+
+
+#[derive(Error, Debug)]
+#[allow(clippy::enum_variant_names)]
+pub enum CommandError {
+    #[error("The data file can't be read, it was probably corrupted ({0}). Run ATA without a command to recover it")]
+    BrokenDataFileError(String),
+
+    #[error("Game installation not found at {0:?} (it doesn't contain NieRAutomata.exe), set the right one with \"ata config set game-path <path>\"")]
+    GameNotFoundError(PathBuf),
+
+    #[error("The required modding files are missing ({0:?}), run ATA without a command to install them")]
+    MissingModdingFilesError(Vec<PathBuf>),
+}
+impl CommandError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            CommandError::BrokenDataFileError(_) => "broken_data_file",
+            CommandError::GameNotFoundError(_) => "game_not_found",
+            CommandError::MissingModdingFilesError(_) => "modding_files_missing",
+        }
+    }
+}
+
+
+
+/* -------------------- */
+/*   RUNNING COMMANDS   */
+/* -------------------- */
+
+// Exits with a failure also when the command worked but found problems (doctor)
+pub fn run_command(command: Command) -> Result<ExitCode, Box<dyn Error>> {
+    // The doctor has to work even when the data file doesn't
+    if let Command::Doctor = command {
+        return run_doctor();
+    }
+
+    let mut config = load_config()?;
+
+    match command {
+        Command::Install { mod_path, name, mod_type, on_conflict } => {
+            check_game_folder(&config)?;
+
+            install_mod(&mut config, &mod_path, InstallChoices::Given { name, mod_type, on_conflict })?;
+            config.save_config()?;
+
+            if let Some(installed_mod) = config.mods.last() {
+                log_event(&format!("Installed \"{}\" (ID: {}) from {:?}", installed_mod.name(), installed_mod.id(), mod_path));
+                print_mod_result("installed", installed_mod)?;
+            }
+        }
+        Command::Uninstall { mod_name, remove_modified } => {
+            check_game_folder(&config)?;
+
+            let modified_files = if remove_modified { ModifiedFiles::Remove } else { ModifiedFiles::Keep };
+            let uninstalled_mod = uninstall_mod(&mut config, &mod_name, modified_files)?;
+            config.save_config()?;
+
+            log_event(&format!("Uninstalled \"{}\" (ID: {})", uninstalled_mod.name(), uninstalled_mod.id()));
+            print_mod_result("uninstalled", &uninstalled_mod)?;
+        }
+        Command::Enable { mod_name } => {
+            check_game_folder(&config)?;
+
+            enable_mod(&mut config, &mod_name)?;
+            config.save_config()?;
+
+            let enabled_mod = installed_mod(&config, &mod_name)?;
+            log_event(&format!("Enabled \"{}\" (ID: {})", enabled_mod.name(), enabled_mod.id()));
+            print_mod_result("enabled", enabled_mod)?;
+        }
+        Command::Disable { mod_name } => {
+            check_game_folder(&config)?;
+
+            disable_mod(&mut config, &mod_name)?;
+            config.save_config()?;
+
+            let disabled_mod = installed_mod(&config, &mod_name)?;
+            log_event(&format!("Disabled \"{}\" (ID: {})", disabled_mod.name(), disabled_mod.id()));
+            print_mod_result("disabled", disabled_mod)?;
+        }
+        Command::List { mod_type, enabled, disabled, name, sort } => {
+            let filter = ModFilter {
+                mod_type,
+                enabled: if enabled { Some(true) } else if disabled { Some(false) } else { None },
+                name,
+            };
+            let listed_mods = filter_and_sort_mods(&config.mods, &filter, sort);
+
+            if json_output() {
+                print_json(&json!({ "mods": listed_mods }))?;
+            } else {
+                list_mods(&listed_mods);
+            }
+        }
+        Command::Info { mod_name } => {
+            let info = mod_info(&config, installed_mod(&config, &mod_name)?);
+            if json_output() {
+                print_json(&info)?;
+            } else {
+                show_mod_info(&info);
+            }
+        }
+        Command::Repair => {
+            check_game_folder(&config)?;
+
+            let report = repair_game_folder(&mut config)?;
+            config.save_config()?;
+
+            log_event(&format!("Repaired the game folder ({} files put back)", report.redeployed_files.len()));
+            if json_output() {
+                print_json(&report)?;
+            } else {
+                show_repair_report(&report);
+            }
+
+            // What's missing from the mod store can't be repaired
+            if !report.broken_stored_files.is_empty() {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Command::Order { action: OrderAction::List } => print_load_order(&config)?,
+        Command::Order { action: OrderAction::Move { mod_name, before, after } } => {
+            check_game_folder(&config)?;
+
+            let place = match (before, after) {
+                (Some(other_mod_name), _) => OrderPlace::Before(other_mod_name),
+                (None, Some(other_mod_name)) => OrderPlace::After(other_mod_name),
+                (None, None) => unreachable!("clap requires either --before or --after"),
+            };
+            let moved_mod = move_mod(&mut config, &mod_name, &place)?;
+            let (moved_mod_name, moved_mod_id) = (moved_mod.name().to_string(), moved_mod.id());
+            config.save_config()?;
+
+            log_event(&format!("Moved \"{}\" (ID: {}) in the load order", moved_mod_name, moved_mod_id));
+            if !json_output() {
+                println!("MOD \"{}\" MOVED (ID: {})\n", moved_mod_name, moved_mod_id);
+            }
+            print_load_order(&config)?;
+        }
+        Command::Config { action: ConfigAction::Get { key } } => print_setting(&config, key)?,
+        Command::Config { action: ConfigAction::Set { key, value } } => {
+            match key {
+                ConfigKey::GamePath => {
+                    let game_path = PathBuf::from(value);
+                    // A folder that can't be read can't be the game either
+                    if !check_path(&game_path).unwrap_or(false) {
+                        return Err(CommandError::GameNotFoundError(game_path).into());
+                    }
+
+                    config.game_path = game_path;
+                }
+            }
+            config.save_config()?;
+
+            log_event(&format!("Changed the game path to {:?}", config.game_path));
+            print_setting(&config, key)?;
+        }
+        Command::Doctor => unreachable!("the doctor runs before loading the config"),
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn load_config() -> Result<Config, Box<dyn Error>> {
+    Config::load_config().map_err(data_file_error)
+}
+
+fn data_file_error(er: Box<dyn Error>) -> Box<dyn Error> {
+    if is_broken_data_file_error(er.as_ref()) {
+        CommandError::BrokenDataFileError(er.to_string()).into()
+    } else {
+        er
+    }
+}
+
+// Commands that change the game folder need it to be the right one, with the required modding files installed
+// The interactive menu asks to fix these, commands can only tell what's wrong
+fn check_game_folder(config: &Config) -> Result<(), Box<dyn Error>> {
+    if !check_path(&config.game_path).unwrap_or(false) {
+        return Err(CommandError::GameNotFoundError(config.game_path.clone()).into());
+    }
+
+    let missing_required_modding_files = check_for_required_modding_files(&config.game_path);
+    if !missing_required_modding_files.is_empty() {
+        return Err(CommandError::MissingModdingFilesError(missing_required_modding_files).into());
+    }
+
+    Ok(())
+}
+
+// {"installed": {...the mod...}} or MOD "name" INSTALLED (ID: 1)
+fn print_mod_result(action: &str, changed_mod: &Mod) -> Result<(), Box<dyn Error>> {
+    if json_output() {
+        print_json(&json!({ action: changed_mod }))?;
+    } else {
+        println!("MOD \"{}\" {} (ID: {})", changed_mod.name(), action.to_uppercase(), changed_mod.id());
+    }
+
+    Ok(())
+}
+
+fn print_load_order(config: &Config) -> Result<(), Box<dyn Error>> {
+    let load_order = load_order(config);
+    if json_output() {
+        print_json(&json!({ "load_order": load_order }))?;
+    } else {
+        show_load_order(&load_order);
+    }
+
+    Ok(())
+}
+
+fn print_setting(config: &Config, key: ConfigKey) -> Result<(), Box<dyn Error>> {
+    match key {
+        ConfigKey::GamePath => {
+            if json_output() {
+                print_json(&json!({ "game_path": config.game_path }))?;
+            } else {
+                println!("{}", config.game_path.display());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+
+
+/* ---------- */
+/*   DOCTOR   */
+/* ---------- */
+
+// Same checks ATA does when the interactive menu starts, without fixing anything
+#[derive(Serialize)]
+struct DoctorReport {
+    data_file: PathBuf,
+    data_file_found: bool,                  // ATA creates it with default values the first time it runs
+    data_file_error: Option<String>,        // Why the data file can't be loaded, the game isn't checked in that case
+    game_path: Option<PathBuf>,
+    game_found: bool,
+    missing_modding_files: Vec<PathBuf>,
+    healthy: bool,                          // Whether everything is fine and mods can be managed
+}
+
+fn run_doctor() -> Result<ExitCode, Box<dyn Error>> {
+    let mut report = DoctorReport {
+        data_file: data_file_path(),
+        data_file_found: false,
+        data_file_error: None,
+        game_path: None,
+        game_found: false,
+        missing_modding_files: vec![],
+        healthy: false,
+    };
+
+    // The doctor only looks, a missing data file is checked as the default one ATA would create
+    match Config::read_config().map_err(data_file_error) {
+        Ok(config) => {
+            report.data_file_found = config.is_some();
+            let config = config.unwrap_or_default();
+
+            // A game path that can't be read is a game that isn't there
+            report.game_found = check_path(&config.game_path).unwrap_or(false);
+            if report.game_found {
+                report.missing_modding_files = check_for_required_modding_files(&config.game_path);
+            }
+            report.game_path = Some(config.game_path);
+        }
+        Err(er) => report.data_file_error = Some(er.to_string()),
+    }
+    report.healthy = report.data_file_error.is_none() && report.game_found && report.missing_modding_files.is_empty();
+
+    if json_output() {
+        print_json(&report)?;
+    } else {
+        match &report.data_file_error {
+            None if !report.data_file_found => println!("Data file: NOT CREATED YET ({:?}), ATA creates it with default values when it runs", report.data_file),
+            None => println!("Data file: OK ({:?})", report.data_file),
+            Some(er) => println!("Data file: PROBLEM ({:?}) {}", report.data_file, er),
+        }
+        if let Some(game_path) = &report.game_path {
+            if report.game_found {
+                println!("Game installation: OK ({:?})", game_path);
+            } else {
+                println!("Game installation: NOT FOUND at {:?} (it doesn't contain NieRAutomata.exe)", game_path);
+            }
+        }
+        if report.game_found {
+            if report.missing_modding_files.is_empty() {
+                println!("Required modding files: OK");
+            } else {
+                println!("Required modding files: MISSING {:?}", report.missing_modding_files);
+            }
+        }
+    }
+
+    Ok(if report.healthy { ExitCode::SUCCESS } else { ExitCode::FAILURE })
+}
@@ -0,0 +1,208 @@
+use std::error::Error;
+
+use std::fs::read_dir;
+
+use std::path::{Path, PathBuf};
+
+use walkdir::WalkDir;
+
+use crate::data_saving::{
+    config_backups, file_hash, restore_config_backup, set_aside_broken_data_file, Config, InstalledFile, Mod, ModComponent, ModType,
+};
+
+use crate::mod_detection::mod_type_in_game_folder;
+
+use crate::mod_store::{copy_file, mod_store_folder, stored_mod_folder};
+
+use crate::starting_checks::check_path;
+
+use crate::user_interactions::{ask_config_backup_to_restore, ask_for_correct_gamepath, ask_recovery_action};
+
+use crate::vanilla_backups::{backup_path, backups_folder};
+
+
+
+// Lets the user choose how to get a working config back when the data file can't be read
+// Returns None if the user would rather close ATA and fix the file by hand
+pub fn recover_config() -> Result<Option<Config>, Box<dyn Error>> {
+    loop {
+        let backups = config_backups()?;
+
+        match ask_recovery_action(backups.len())?.as_str() {
+            "1" => {
+                let Some(backup_index) = ask_config_backup_to_restore(&backups)? else {
+                    continue;
+                };
+
+                match restore_config_backup(&backups[backup_index].0) {
+                    Ok((config, broken_file_path)) => {
+                        println!("Data file restored (the broken one was kept at {:?})\n", broken_file_path);
+                        return Ok(Some(config));
+                    }
+                    Err(er) => eprintln!("This copy of the data file can't be used either. {}", er),
+                }
+            }
+            "2" => return rebuild_config().map(Some),
+            "3" => {
+                let broken_file_path = set_aside_broken_data_file()?;
+                let config = Config::default();
+                config.save_config()?;
+
+                println!("Starting with no mods (the broken data file was kept at {:?})\n", broken_file_path);
+                return Ok(Some(config));
+            }
+            "0" => return Ok(None),
+            action_id => println!("\"{}\" is not a valid action id (input either 1, 2, 3 or 0)", action_id),
+        }
+    }
+}
+
+// Makes a new config out of what ATA left on the disk
+// Every mod is in the mod store, in the folder named after its ID, and was enabled if its files are in the game folder as they are there
+// Other game files are only adopted if ATA surely put them there (it backed up the original they replaced), in a single mod
+// since there's no way to tell which mod they came from, the rest (textures installed by hand...) is listed and left alone
+fn rebuild_config() -> Result<Config, Box<dyn Error>> {
+    let broken_file_path = set_aside_broken_data_file()?;
+    let mut config = Config::default();
+
+    while !check_path(&config.game_path)? {
+        println!("Game installation not found at {:?} (it doesn't contain NieRAutomata.exe)", config.game_path);
+        config.game_path = ask_for_correct_gamepath()?;
+    }
+
+    // MODS IN THE STORE
+    let mut stored_mods: Vec<(u32, PathBuf)> = vec![];
+    if mod_store_folder().exists() {
+        for entry in read_dir(mod_store_folder())? {
+            let stored_mod_path = entry?.path();
+            let mod_id = stored_mod_path
+                .file_name()
+                .and_then(|folder_name| folder_name.to_str())
+                .and_then(|folder_name| folder_name.parse::<u32>().ok());
+
+            if let Some(mod_id) = mod_id && stored_mod_path.is_dir() {
+                stored_mods.push((mod_id, stored_mod_path));
+            }
+        }
+    }
+    stored_mods.sort();
+
+    for (mod_id, stored_mod_path) in stored_mods {
+        let stored_files = files_in_folder(&stored_mod_path)?;
+        let components = components_from_files(&stored_mod_path, &stored_files)?;
+
+        let mut deployed_files: Vec<PathBuf> = vec![];
+        for installed_file in components.iter().flat_map(ModComponent::files) {
+            let game_file_path = config.game_path.join(&installed_file.path);
+            if game_file_path.is_file() && file_hash(&game_file_path)? == installed_file.hash {
+                deployed_files.push(installed_file.path.clone());
+            }
+        }
+        let vanilla_backups: Vec<PathBuf> = deployed_files.iter().filter(|deployed_file| backup_path(deployed_file).is_file()).cloned().collect();
+
+        // Only the files that replaced an original are surely ATA's copies, the others could be originals the mod has unchanged
+        // and are left in the game folder when the mod goes away
+        config.deployed_files.extend(vanilla_backups.iter().cloned());
+
+        if !components.is_empty() {
+            let recovered_mod = Mod::new(format!("Recovered mod {}", mod_id), None, components, vanilla_backups, !deployed_files.is_empty());
+            config.add_mod_with_id(recovered_mod, mod_id);
+        }
+    }
+
+    // FILES NO STORED MOD HAS
+    let mut adopted_files: Vec<PathBuf> = vec![];
+    for backup_file in files_in_folder(&backups_folder())? {
+        let game_relative_path = backup_file.strip_prefix(backups_folder())?;
+        let game_file_path = config.game_path.join(game_relative_path);
+
+        if game_file_path.is_file() && !is_stored_copy(&config, game_relative_path, &game_file_path)? {
+            adopted_files.push(game_file_path);
+        }
+    }
+
+    let mut unknown_files: Vec<PathBuf> = vec![];
+    for texture_file in files_in_folder(&config.game_path.join("SK_Res/inject/textures"))? {
+        let game_relative_path = texture_file.strip_prefix(&config.game_path)?;
+        if !adopted_files.contains(&texture_file) && !is_stored_copy(&config, game_relative_path, &texture_file)? {
+            unknown_files.push(game_relative_path.to_path_buf());
+        }
+    }
+
+    let components = components_from_files(&config.game_path, &adopted_files)?;
+    if !components.is_empty() {
+        // They go in the store too, like the files of every other mod
+        let mod_id = config.next_mod_id();
+        let mut vanilla_backups: Vec<PathBuf> = vec![];
+        for adopted_file in &adopted_files {
+            let game_relative_path = adopted_file.strip_prefix(&config.game_path)?;
+            copy_file(adopted_file, &stored_mod_folder(mod_id).join(game_relative_path))?;
+            vanilla_backups.push(game_relative_path.to_path_buf());
+        }
+        config.deployed_files.extend(vanilla_backups.iter().cloned());
+
+        config.add_mod(Mod::new(String::from("Recovered mods"), None, components, vanilla_backups, true));
+    }
+
+    if !unknown_files.is_empty() {
+        println!("These files in the game folder don't come from any mod ATA knows, they were left as they are:");
+        for unknown_file in &unknown_files {
+            println!("\t{:?}", unknown_file);
+        }
+        println!();
+    }
+
+    config.save_config()?;
+    println!("{} mods recovered (the broken data file was kept at {:?})\n", config.mods.len(), broken_file_path);
+
+    Ok(config)
+}
+
+// Whether the game file is the copy of one of the recovered mods
+fn is_stored_copy(config: &Config, game_relative_path: &Path, game_file_path: &Path) -> Result<bool, Box<dyn Error>> {
+    let stored_copies: Vec<&InstalledFile> = config.mods.iter().filter_map(|recovered_mod| recovered_mod.installed_file(game_relative_path)).collect();
+    if stored_copies.is_empty() {
+        return Ok(false);
+    }
+
+    let game_file_hash = file_hash(game_file_path)?;
+    Ok(stored_copies.iter().any(|stored_copy| stored_copy.hash == game_file_hash))
+}
+
+// Every file inside the folder and its subfolders, nothing if the folder doesn't exist
+fn files_in_folder(folder: &Path) -> Result<Vec<PathBuf>, Box<dyn Error>> {
+    if !folder.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut files: Vec<PathBuf> = vec![];
+    for entry in WalkDir::new(folder).sort_by_file_name() {
+        let current_entry = entry?;
+        if current_entry.file_type().is_file() {
+            files.push(current_entry.into_path());
+        }
+    }
+
+    Ok(files)
+}
+
+// Groups the files by the game folder they are (or go) in, files in other folders are left out
+// The base folder is the one the paths are relative to (the game folder, or the folder of a mod in the store)
+fn components_from_files(base_folder: &Path, files: &[PathBuf]) -> Result<Vec<ModComponent>, Box<dyn Error>> {
+    let mut components: Vec<ModComponent> = vec![];
+
+    for mod_type in ModType::ALL {
+        let mut component_files: Vec<InstalledFile> = vec![];
+        for file_path in files {
+            if mod_type_in_game_folder(file_path.strip_prefix(base_folder)?) == Some(mod_type) {
+                component_files.push(InstalledFile::from_installed_path(base_folder, file_path)?);
+            }
+        }
+
+        if !component_files.is_empty() {
+            components.push(ModComponent::new(mod_type, component_files));
+        }
+    }
+
+    Ok(components)
+}
@@ -1,112 +1,599 @@
+use std::collections::{BTreeMap, BTreeSet};
+
 use std::error::Error;
 
-use std::io::{BufReader, Write};
+use std::io::{self, BufReader, Write};
 
-use std::fs::{File, create_dir_all};
+use std::fs::{File, copy, create_dir_all, read_dir, remove_file, rename};
 
-use std::env::var;
+use std::path::{Path, PathBuf};
 
-use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use serde::{Serialize, Deserialize};
 
+use serde_json::{Map, Value};
+
+use sha2::{Digest, Sha256};
+
+use time::{macros::format_description, OffsetDateTime};
+
+use thiserror::Error;
+
+use crate::ata_folders::ata_folders;
+
+use crate::ata_log::log_event;
+
+use crate::output::print_notice;
+
 
 
 // The various types of mod that can be installed with ATA
-#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(serde::Serialize, serde::Deserialize, clap::ValueEnum, Debug, Clone, Copy, PartialEq)]
 pub enum ModType {
     Textures,
-    PlayerModels,
-    WeaponModels,
-    WorldModels,
+    PlayerModels,           // data/pl
+    WeaponModels,           // data/wp
+    WorldModels,            // data/bg
+    EnemyModels,            // data/em
+    ObjectModels,           // data/um
+    EffectModels,           // data/et
+    InterfaceFiles,         // data/ui
+    ItemModels,             // data/it
     CutsceneReplacements,
+    #[value(skip)]              // Not supported yet, it can't be chosen when installing
     ReshadePreset,
 }
+impl ModType {
+    // Types ATA can install the files of, the ones the user can choose from (ReShade presets aren't supported yet)
+    pub const ALL: [ModType; 10] = [
+        ModType::Textures,
+        ModType::PlayerModels,
+        ModType::WeaponModels,
+        ModType::WorldModels,
+        ModType::EnemyModels,
+        ModType::ObjectModels,
+        ModType::EffectModels,
+        ModType::InterfaceFiles,
+        ModType::ItemModels,
+        ModType::CutsceneReplacements,
+    ];
+}
+
+// A file a mod puts in the game folder
+#[derive(Serialize, Deserialize, Clone)]
+pub struct InstalledFile {
+    pub path: PathBuf,      // Where the file is, relative to the game folder (data/pl/pl0000.dtt)
+    pub size: u64,          // Size in bytes
+    pub hash: String,       // SHA-256 of the contents, tells if the file was changed after the installation
+}
+impl InstalledFile {
+    // Takes note of a file that was just installed, in the mod store or in the game folder
+    // The base folder is the one the game expects the path to be relative to (the game folder, or the folder of the mod in the store)
+    pub fn from_installed_path(base_folder: &Path, installed_path: &Path) -> Result<Self, Box<dyn Error>> {
+        let path = installed_path.strip_prefix(base_folder)?.to_path_buf();
+        let size = installed_path.metadata()?.len();
+        let hash = file_hash(installed_path)?;
+
+        Ok(Self { path, size, hash })
+    }
+}
+
+// SHA-256 of the contents of a file, as a hex string
+pub fn file_hash(file_path: &Path) -> Result<String, io::Error> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(file_path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+// Part of a mod containing a single type of files (a mod can ship textures, models and cutscenes together)
+#[derive(Serialize, Deserialize)]
+pub struct ModComponent {
+    mod_type: ModType,              // Type of the files of this part of the mod
+    files: Vec<InstalledFile>,      // Files installed by this part of the mod (not the folders containing them, all files one by one)
+}
+impl ModComponent {
+    pub fn new(mod_type: ModType, files: Vec<InstalledFile>) -> Self {
+        Self {
+            mod_type,
+            files,
+        }
+    }
+
+    pub fn mod_type(&self) -> ModType {
+        self.mod_type
+    }
+
+    pub fn files(&self) -> &[InstalledFile] {
+        &self.files
+    }
+}
 
 // Things to take note about a mod for both mod managing and informing the user
 #[derive(Serialize, Deserialize)]
 pub struct Mod {
-    name: String,           // Name of the mod given by the user
-    files: Vec<PathBuf>,    // Files used by the mod (not the folder contaning, list of all files one by one)
-    enabled: bool,          // Whether the mod is enabled or not
-    mod_type: ModType,      // Type of the mod 
+    id: u32,                            // Number identifying the mod, given by ATA when the mod is added to the config
+    name: String,                       // Name of the mod given by the user
+    source: Option<PathBuf>,            // Archive, folder or file the mod was installed from (None if it isn't known)
+    components: Vec<ModComponent>,      // Parts of the mod, one for each type of files it contains
+    vanilla_backups: Vec<PathBuf>,      // Original game files the mod overwrote, relative to the game folder (the copies are in the backups folder)
+    enabled: bool,                      // Whether the mod is enabled or not
+    installed_at: u64,                  // When the mod was installed, in seconds since 1970 (0 if it was installed before ATA saved it)
+    updated_at: u64,                    // Last time the user changed the mod (enabled, disabled, moved...), in seconds since 1970
 }
 impl Mod {
-    pub fn new(name: String, files: Vec<PathBuf>, enabled: bool, mod_type: ModType) -> Self {
+    pub fn new(name: String, source: Option<PathBuf>, components: Vec<ModComponent>, vanilla_backups: Vec<PathBuf>, enabled: bool) -> Self {
+        let now = seconds_since_epoch();
+
         Self {
+            id: 0,
             name,
-            files,
+            source,
+            components,
+            vanilla_backups,
             enabled,
-            mod_type,
+            installed_at: now,
+            updated_at: now,
+        }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn components(&self) -> &[ModComponent] {
+        &self.components
+    }
+
+    pub fn vanilla_backups(&self) -> &[PathBuf] {
+        &self.vanilla_backups
+    }
+
+    pub fn set_vanilla_backups(&mut self, vanilla_backups: Vec<PathBuf>) {
+        self.vanilla_backups = vanilla_backups;
+    }
+
+    // What the mod installed at the path relative to the game folder
+    pub fn installed_file(&self, game_relative_path: &Path) -> Option<&InstalledFile> {
+        self.components.iter().flat_map(ModComponent::files).find(|installed_file| installed_file.path == game_relative_path)
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+
+    pub fn source(&self) -> Option<&Path> {
+        self.source.as_deref()
+    }
+
+    pub fn installed_at(&self) -> u64 {
+        self.installed_at
+    }
+
+    pub fn updated_at(&self) -> u64 {
+        self.updated_at
+    }
+
+    // Called once something the user did to the mod went through, what ATA changes by itself while deploying doesn't count
+    pub fn mark_updated(&mut self) {
+        self.updated_at = seconds_since_epoch();
+    }
+
+    // Types of the parts of the mod, each one only once
+    pub fn mod_types(&self) -> Vec<ModType> {
+        let mut mod_types: Vec<ModType> = vec![];
+        for component in &self.components {
+            if !mod_types.contains(&component.mod_type) {
+                mod_types.push(component.mod_type);
+            }
         }
+        mod_types
     }
+
+    pub fn file_count(&self) -> usize {
+        self.components.iter().map(|component| component.files.len()).sum()
+    }
+
+    // Size of all the files of the mod, in bytes
+    pub fn total_size(&self) -> u64 {
+        self.components
+            .iter()
+            .flat_map(|component| &component.files)
+            .map(|installed_file| installed_file.size)
+            .sum()
+    }
+}
+
+// Current time as seconds since 1970 (UTC), how ATA saves dates
+pub fn seconds_since_epoch() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+// Date saved by ATA as 2025-01-31 18:30 UTC, "unknown" for mods installed before dates were saved
+pub fn format_timestamp(seconds_since_epoch: u64) -> String {
+    let date_format = format_description!("[year]-[month]-[day] [hour]:[minute] UTC");
+
+    i64::try_from(seconds_since_epoch)
+        .ok()
+        .filter(|seconds| *seconds > 0)
+        .and_then(|seconds| OffsetDateTime::from_unix_timestamp(seconds).ok())
+        .and_then(|date| date.format(&date_format).ok())
+        .unwrap_or(String::from("unknown"))
+}
+
+// Where the config is saved
+pub fn data_file_path() -> PathBuf {
+    ata_folders().config.join("data.json")
 }
 
 // What to save in the data file
 #[derive(Serialize, Deserialize)]
 pub struct Config {
+    schema_version: u32,        // Layout of the data file, tells which migrations it needs when loaded by a newer ATA
     pub game_path: PathBuf,
     pub mods: Vec<Mod>,
+    pub conflict_winners: BTreeMap<PathBuf, u32>,     // Mod whose copy goes in the game folder, for the files more than one mod has (by conflict key)
+    pub deployed_files: BTreeSet<PathBuf>,            // Game files that are copies ATA put there, anything else in the game folder isn't ATA's to remove
 }
 impl Config {
     // Save the config to file
-    // fn save_config(&self) -> Result<(), Box<dyn std::error::Error>> {
-    //     let data_file = File::create(DATA_FILE_PATH)?;
-    //     serde_json::to_writer_pretty(data_file, self)?;
-    //     Ok(())
-    // }
+    // The data is written to a temporary file that then takes the place of the old one,
+    // so the data file is always either the old or the new one, never half written (if ATA or the PC crashes while saving)
+    // A copy also goes in the config backups, to recover from a data file broken some other way
+    pub fn save_config(&self) -> Result<(), Box<dyn Error>> {
+        let contents = serde_json::to_string_pretty(self)?;
+        write_atomically(&data_file_path(), contents.as_bytes())?;
+        back_up_config(contents.as_bytes())?;
+        Ok(())
+    }
 
     // Load the config from file, or load a default one
+    // Files saved by older versions of ATA are upgraded to the current schema (the old file is kept next to it)
     pub fn load_config() -> Result<Self, Box<dyn Error>>
     {
-        let home_dir = var("HOME").unwrap_or(String::from("/home/2B/"));
-        let data_file_path = PathBuf::from(home_dir)
-            .join(".config")
-            .join("ATA")
-            .join("data.json");
+        let data_file_path = data_file_path();
 
         if data_file_path.exists() {
-            let data_file = File::open(data_file_path)?;
-            let reader = BufReader::new(data_file);
-            let contents = serde_json::from_reader(reader)?;
+            let (config, file_version) = read_config_file(&data_file_path)?;
+
+            if file_version < CURRENT_SCHEMA_VERSION {
+                let mut old_file_name = data_file_path.file_name().unwrap_or_default().to_os_string();
+                old_file_name.push(format!(".v{}.bak", file_version));
+                let old_file_path = data_file_path.with_file_name(old_file_name);
+                copy(&data_file_path, &old_file_path)?;
 
-            Ok(contents)
+                config.save_config()?;
+                print_notice(&format!("Data file upgraded from version {} to version {} (the old one was kept at {:?})\n", file_version, CURRENT_SCHEMA_VERSION, old_file_path));
+                log_event(&format!("Upgraded the data file from schema version {} to {}", file_version, CURRENT_SCHEMA_VERSION));
+            }
+
+            Ok(config)
         }
         else {
-            println!("Config file (~/.config/ATA/data.json) not found, creating it with default values...\n");
+            print_notice(&format!("Config file ({:?}) not found, creating it with default values...\n", data_file_path));
 
             Self::create_default_config_file(data_file_path)
         }   
     }
 
+    // Reads the config from file without changing anything on disk, for checks that only report
+    // The file isn't created if it's missing (None), and files saved by older versions of ATA are only upgraded in memory
+    pub fn read_config() -> Result<Option<Self>, Box<dyn Error>> {
+        let data_file_path = data_file_path();
+        if !data_file_path.exists() {
+            return Ok(None);
+        }
+
+        let (config, _) = read_config_file(&data_file_path)?;
+        Ok(Some(config))
+    }
+
+    // Adds a newly installed mod, giving it the first free ID
+    pub fn add_mod(&mut self, mut new_mod: Mod) {
+        new_mod.id = self.next_mod_id();
+        self.mods.push(new_mod);
+    }
+
+    // ID the next installed mod will get
+    pub fn next_mod_id(&self) -> u32 {
+        self.mods.iter().map(|saved_mod| saved_mod.id + 1).max().unwrap_or(1)
+    }
+
+    // Adds a mod that already has an ID, used for the mods found in the mod store (where their folders are named after it)
+    pub fn add_mod_with_id(&mut self, mut new_mod: Mod, id: u32) {
+        new_mod.id = id;
+        self.mods.push(new_mod);
+    }
+
+    // Position in the list of the mod with the given ID or name
+    pub fn find_mod(&self, id_or_name: &str) -> Option<usize> {
+        let id_or_name = id_or_name.trim();
+
+        id_or_name
+            .parse::<u32>()
+            .ok()
+            .and_then(|id| self.mods.iter().position(|saved_mod| saved_mod.id == id))
+            .or_else(|| self.mods.iter().position(|saved_mod| saved_mod.name == id_or_name))
+            .or_else(|| self.mods.iter().position(|saved_mod| saved_mod.name.eq_ignore_ascii_case(id_or_name)))
+    }
+
     // creates a default config and saves it to the file
     fn create_default_config_file(path: PathBuf) -> Result<Self, Box<dyn Error>> {
         let default_config = Self::default();
-        
-        if let Some(data_file_folder) = path.parent() {
-            create_dir_all(data_file_folder)?;
-        };
-        
-        let mut default_config_file = File::create(path)?;
 
         let default_config_json = serde_json::to_string_pretty(&default_config)?;
-        default_config_file.write_all(default_config_json.as_bytes())?;
+        write_atomically(&path, default_config_json.as_bytes())?;
 
         Ok(default_config)
     }
 }
+
+// Reads a data file, upgrading it in memory to the current schema
+// Also returns the schema version the file was saved with
+fn read_config_file(path: &Path) -> Result<(Config, u32), Box<dyn Error>> {
+    let data_file = File::open(path)?;
+    let reader = BufReader::new(data_file);
+    let mut contents: Value = serde_json::from_reader(reader)?;
+
+    let file_version = schema_version_of(&contents)?;
+    if file_version > CURRENT_SCHEMA_VERSION {
+        return Err(ConfigError::NewerSchemaError { found: file_version, supported: CURRENT_SCHEMA_VERSION }.into());
+    }
+
+    migrate_config(&mut contents, file_version)?;
+    Ok((serde_json::from_value(contents)?, file_version))
+}
+
+// Replaces the contents of a file without ever leaving it half written
+// Writes a temporary file next to it, flushes it to the disk and renames it over the old one (renaming is atomic on the same filesystem)
+fn write_atomically(path: &Path, contents: &[u8]) -> Result<(), io::Error> {
+    let Some(folder) = path.parent() else {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{:?} is not a file path", path)));
+    };
+    create_dir_all(folder)?;
+
+    let mut temporary_file_name = path.file_name().unwrap_or_default().to_os_string();
+    temporary_file_name.push(".tmp");
+    let temporary_path = folder.join(temporary_file_name);
+
+    let mut temporary_file = File::create(&temporary_path)?;
+    temporary_file.write_all(contents)?;
+    temporary_file.sync_all()?;
+
+    rename(&temporary_path, path)?;
+
+    // The rename itself is only on the disk once the folder is
+    File::open(folder)?.sync_all()
+}
 impl Default for Config {
     // Creates default config
-    // Default game_path = $HOME(or /home/2B/)/.local/share/Steam/steamapps/common/NieRAutomata
+    // Default game_path = $XDG_DATA_HOME(or $HOME/.local/share)/Steam/steamapps/common/NieRAutomata
     // Default mods = empty list
     fn default() -> Self {
-        let home_dir = var("HOME").unwrap_or(String::from("/home/2B/"));
-        let default_game_path = PathBuf::from(home_dir)
-            .join(".local/share/Steam/steamapps/common/NieRAutomata");
+        let default_game_path = ata_folders().user_data
+            .join("Steam/steamapps/common/NieRAutomata");
 
         Self {
+            schema_version: CURRENT_SCHEMA_VERSION,
             game_path: default_game_path,
             mods: Default::default(),
+            conflict_winners: Default::default(),
+            deployed_files: Default::default(),
         }
     }
-}
\ No newline at end of file
+}
+
+
+/* ---------------------- */
+/*   DATA FILE RECOVERY   */
+/* ---------------------- */
+
+// How many copies of the data file are kept, older ones get deleted
+const KEPT_CONFIG_BACKUPS: usize = 5;
+
+fn config_backups_folder() -> PathBuf {
+    ata_folders().state.join("config-backups")
+}
+
+// Keeps a copy of the data file just saved (backups/data-<seconds since 1970>.json), removing the oldest ones
+fn back_up_config(contents: &[u8]) -> Result<(), io::Error> {
+    let backup_path = config_backups_folder().join(format!("data-{}.json", seconds_since_epoch()));
+    write_atomically(&backup_path, contents)?;
+
+    for (old_backup_path, _) in config_backups()?.into_iter().skip(KEPT_CONFIG_BACKUPS) {
+        remove_file(old_backup_path)?;
+    }
+
+    Ok(())
+}
+
+// Copies of the data file, newest first, each with the time it was made (seconds since 1970)
+pub fn config_backups() -> Result<Vec<(PathBuf, u64)>, io::Error> {
+    let backups_folder = config_backups_folder();
+    if !backups_folder.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut backups: Vec<(PathBuf, u64)> = vec![];
+    for entry in read_dir(backups_folder)? {
+        let backup_path = entry?.path();
+        let backup_time = backup_path
+            .file_name()
+            .and_then(|file_name| file_name.to_str())
+            .and_then(|file_name| file_name.strip_prefix("data-"))
+            .and_then(|file_name| file_name.strip_suffix(".json"))
+            .and_then(|seconds| seconds.parse::<u64>().ok());
+
+        if let Some(backup_time) = backup_time {
+            backups.push((backup_path, backup_time));
+        }
+    }
+    backups.sort_by(|(_, first_time), (_, second_time)| second_time.cmp(first_time));
+
+    Ok(backups)
+}
+
+// Moves the data file that can't be read next to it (data.json.broken-<seconds since 1970>), so the user can still look at it
+// Returns where it was moved
+pub fn set_aside_broken_data_file() -> Result<PathBuf, io::Error> {
+    let data_file_path = data_file_path();
+    let mut broken_file_name = data_file_path.file_name().unwrap_or_default().to_os_string();
+    broken_file_name.push(format!(".broken-{}", seconds_since_epoch()));
+    let broken_file_path = data_file_path.with_file_name(broken_file_name);
+
+    rename(&data_file_path, &broken_file_path)?;
+    Ok(broken_file_path)
+}
+
+// Replaces the data file that can't be read with one of its copies
+// The copy is checked before touching anything, and the broken file is set aside
+pub fn restore_config_backup(backup_path: &Path) -> Result<(Config, PathBuf), Box<dyn Error>> {
+    let (config, _) = read_config_file(backup_path)?;
+
+    let broken_file_path = set_aside_broken_data_file()?;
+    config.save_config()?;
+
+    Ok((config, broken_file_path))
+}
+
+// Whether loading the config failed because of what's in the data file (so it can be recovered), not because of the disk or of ATA
+pub fn is_broken_data_file_error(error: &(dyn Error + 'static)) -> bool {
+    error.is::<serde_json::Error>()
+        || error
+            .downcast_ref::<ConfigError>()
+            .is_some_and(|config_error| !matches!(config_error, ConfigError::NewerSchemaError { .. }))
+}
+
+
+
+/* ------------------------ */
+/*   DATA FILE MIGRATIONS   */
+/* ------------------------ */
+
+#[derive(Error, Debug)]
+#[allow(clippy::enum_variant_names)]
+pub enum ConfigError {
+    #[error("The data file was saved by a newer version of ATA (schema version {found}, this one only knows up to {supported}), update ATA to use it")]
+    NewerSchemaError { found: u32, supported: u32 },
+
+    #[error("The data file has an invalid schema version ({0})")]
+    SchemaVersionReadingError(String),
+
+    #[error("The data file could not be upgraded from schema version {from}: {reason}")]
+    MigrationError { from: u32, reason: String },
+}
+impl ConfigError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ConfigError::NewerSchemaError { .. } => "data_file_too_new",
+            ConfigError::SchemaVersionReadingError(_) => "invalid_schema_version",
+            ConfigError::MigrationError { .. } => "migration_failed",
+        }
+    }
+}
+
+// Each migration upgrades the data file by one version (the first one goes from 0 to 1, the second from 1 to 2...)
+// Adding a field to Config or Mod means filling it in for older files, in a new migration once the current schema has shipped
+type Migration = fn(&mut Value) -> Result<(), ConfigError>;
+const MIGRATIONS: [Migration; 1] = [
+    migrate_from_unversioned,
+];
+const CURRENT_SCHEMA_VERSION: u32 = MIGRATIONS.len() as u32;
+
+// Version of the schema the data file follows, files saved before versioning have none and count as 0
+fn schema_version_of(contents: &Value) -> Result<u32, ConfigError> {
+    match contents.get("schema_version") {
+        None => Ok(0),
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or(ConfigError::SchemaVersionReadingError(version.to_string())),
+    }
+}
+
+// Runs, in order, every migration the data file needs to reach the current schema version
+fn migrate_config(contents: &mut Value, file_version: u32) -> Result<(), ConfigError> {
+    for (from_version, migration) in MIGRATIONS.iter().enumerate().skip(file_version as usize) {
+        migration(contents)?;
+        contents["schema_version"] = Value::from(from_version as u32 + 1);
+    }
+
+    Ok(())
+}
+
+// 0 -> 1
+// Unversioned files come from ATA versions that only saved the game path, they never had any mod in them
+// (so no conflict was ever solved and no file was ever deployed either)
+fn migrate_from_unversioned(contents: &mut Value) -> Result<(), ConfigError> {
+    let migration_error = |reason: &str| ConfigError::MigrationError { from: 0, reason: reason.to_string() };
+
+    let Some(config) = contents.as_object_mut() else {
+        return Err(migration_error("it is not a JSON object"));
+    };
+    let mods = config.entry("mods").or_insert(Value::Array(vec![]));
+    if !mods.as_array().is_some_and(|mods| mods.is_empty()) {
+        return Err(migration_error("it has mods, but no version of ATA saved them without a schema version"));
+    }
+    config.entry("conflict_winners").or_insert(Value::Object(Map::new()));
+    config.entry("deployed_files").or_insert(Value::Array(vec![]));
+
+    Ok(())
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_user_actions_update_mods() {
+        let mut saved_mod = Mod::new(String::from("Mod"), None, vec![], vec![], true);
+        saved_mod.updated_at = 0;
+
+        // Setters are also used by ATA by itself (deploying, rolling back), they leave the date alone
+        saved_mod.set_vanilla_backups(vec![PathBuf::from("data/pl/pl0000.dtt")]);
+        saved_mod.set_enabled(false);
+        assert_eq!(saved_mod.updated_at(), 0);
+
+        saved_mod.mark_updated();
+        assert_ne!(saved_mod.updated_at(), 0);
+    }
+
+    #[test]
+    fn unversioned_files_are_upgraded() {
+        let mut contents = serde_json::json!({ "game_path": "/games/NieRAutomata", "mods": [] });
+
+        let file_version = schema_version_of(&contents).unwrap();
+        assert_eq!(file_version, 0);
+        migrate_config(&mut contents, file_version).unwrap();
+
+        let config: Config = serde_json::from_value(contents).unwrap();
+        assert_eq!(config.schema_version, CURRENT_SCHEMA_VERSION);
+        assert_eq!(config.game_path, PathBuf::from("/games/NieRAutomata"));
+        assert!(config.mods.is_empty());
+        assert!(config.conflict_winners.is_empty());
+        assert!(config.deployed_files.is_empty());
+    }
+
+    #[test]
+    fn unversioned_files_with_mods_are_refused() {
+        let mut contents = serde_json::json!({
+            "game_path": "/games/NieRAutomata",
+            "mods": [{ "name": "Old mod", "files": [], "enabled": true, "mod_type": "Textures" }]
+        });
+
+        assert!(matches!(migrate_config(&mut contents, 0), Err(ConfigError::MigrationError { from: 0, .. })));
+    }
+}
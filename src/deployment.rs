@@ -0,0 +1,271 @@
+use std::collections::BTreeSet;
+
+use std::error::Error;
+
+use std::fs::remove_file;
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::data_saving::{file_hash, Config, InstalledFile, ModComponent};
+
+use crate::features::remove_empty_folders;
+
+use crate::mod_conflicts::{conflict_key, contested_files, file_with_key, winning_mod};
+
+use crate::mod_detection::{game_folder_of, mod_type_in_game_folder};
+
+use crate::mod_store::{copy_file, stored_mod_folder};
+
+use crate::output::print_notice;
+
+use crate::vanilla_backups::{back_up_vanilla_file, backup_path, restore_vanilla_file};
+
+
+
+// The game folder is where ATA deploys the mods kept in the mod store
+// Each file enabled mods have gets the copy of the mod that wins it, the others get their original back
+// Winners only depend on the config, so the same config and mod store always end up with the same game folder
+
+// Makes the game folder have the copy of the file of the mod that wins it, or the original file if no enabled mod has it
+// Files that take the same place in the game (textures with the same name) are redeployed together, only the winning one stays
+// Called after anything changes about the mods that have the file (installed, uninstalled, enabled, disabled, moved...)
+// Returns whether the game folder changed
+pub fn redeploy_file(config: &mut Config, game_relative_path: &Path) -> Result<bool, Box<dyn Error>> {
+    let key = conflict_key(game_relative_path);
+    let mut slot_paths: BTreeSet<PathBuf> = config.mods
+        .iter()
+        .flat_map(|saved_mod| saved_mod.components().iter().flat_map(ModComponent::files))
+        .filter(|installed_file| conflict_key(&installed_file.path) == key)
+        .map(|installed_file| installed_file.path.clone())
+        .collect();
+    slot_paths.insert(game_relative_path.to_path_buf());
+
+    let winning_mod = winning_mod(config, game_relative_path, true);
+    let winning_file = winning_mod.and_then(|winning_mod| {
+        config.mods
+            .iter()
+            .find(|saved_mod| saved_mod.id() == winning_mod)
+            .and_then(|saved_mod| file_with_key(saved_mod, &key))
+            .cloned()
+    });
+
+    let mut game_folder_changed = false;
+    for slot_path in &slot_paths {
+        let slot_winner = match (winning_mod, &winning_file) {
+            (Some(winning_mod), Some(winning_file)) if &winning_file.path == slot_path => Some((winning_mod, winning_file)),
+            _ => None,
+        };
+
+        if deploy_path(config, slot_path, slot_winner)? {
+            game_folder_changed = true;
+        }
+    }
+
+    // A file only one mod has isn't contested anymore, and a mod without the file can't be the one chosen for it
+    let mods_with_file: Vec<u32> = config.mods
+        .iter()
+        .filter(|saved_mod| file_with_key(saved_mod, &key).is_some())
+        .map(|saved_mod| saved_mod.id())
+        .collect();
+    let chosen_winner_lost_file = config.conflict_winners.get(&key).is_some_and(|chosen_winner| !mods_with_file.contains(chosen_winner));
+    if mods_with_file.len() < 2 || chosen_winner_lost_file {
+        config.conflict_winners.remove(&key);
+    }
+
+    Ok(game_folder_changed)
+}
+
+// Puts the file of the winning mod at the path, or brings the original file back if there's no winner for the path
+fn deploy_path(config: &mut Config, game_relative_path: &Path, winner: Option<(u32, &InstalledFile)>) -> Result<bool, Box<dyn Error>> {
+    let game_path = config.game_path.clone();
+    let game_file = game_path.join(game_relative_path);
+
+    // Only the copies ATA put there are its own, anything else is the original file (or one the user put there)
+    // even with the same contents as a mod file: mods often ship unchanged original files next to the ones they change
+    let deployed_by_ata = config.deployed_files.contains(game_relative_path);
+
+    let mut game_folder_changed = false;
+    match winner {
+        Some((winning_mod, winning_file)) => {
+            // An original file that already is what the mod has stays where it is, as the original
+            let game_file_hash = if game_file.is_file() { Some(file_hash(&game_file)?) } else { None };
+            if game_file_hash.as_ref() != Some(&winning_file.hash) {
+                if !deployed_by_ata {
+                    back_up_vanilla_file(&game_path, game_relative_path)?;
+                }
+
+                let stored_file = stored_mod_folder(winning_mod).join(game_relative_path);
+                if stored_file.is_file() {
+                    copy_file(&stored_file, &game_file)?;
+                    config.deployed_files.insert(game_relative_path.to_path_buf());
+                    game_folder_changed = true;
+                } else {
+                    print_notice(&format!("{:?} is missing from the mod store, it can't be put in the game folder (reinstall the mod to fix it)", game_relative_path));
+                }
+            }
+        }
+        None => {
+            if deployed_by_ata {
+                if game_file.is_file() {
+                    remove_file(&game_file)?;
+                    game_folder_changed = true;
+                }
+                config.deployed_files.remove(game_relative_path);
+            }
+
+            if restore_vanilla_file(&game_path, game_relative_path)? {
+                game_folder_changed = true;
+            } else if backup_path(game_relative_path).is_file() {
+                print_notice(&format!("The original of {:?} was not restored, it is still in the backups folder", game_relative_path));
+            }
+
+            let game_folder = mod_type_in_game_folder(game_relative_path)
+                .and_then(game_folder_of)
+                .map(|game_folder| game_path.join(game_folder))
+                .unwrap_or(game_path.clone());
+            remove_empty_folders(&game_file, &game_folder);
+        }
+    }
+
+    // The original file is kept for the mod that replaces it, disabling or uninstalling that one is what brings it back
+    let backed_up = backup_path(game_relative_path).is_file();
+    let winning_mod = winner.map(|(winning_mod, _)| winning_mod);
+    for saved_mod in config.mods.iter_mut() {
+        let replaces_original = backed_up && Some(saved_mod.id()) == winning_mod;
+        if saved_mod.vanilla_backups().iter().any(|vanilla_file| vanilla_file == game_relative_path) != replaces_original {
+            let mut vanilla_backups = saved_mod.vanilla_backups().to_vec();
+            if replaces_original {
+                vanilla_backups.push(game_relative_path.to_path_buf());
+            } else {
+                vanilla_backups.retain(|vanilla_file| vanilla_file != game_relative_path);
+            }
+            saved_mod.set_vanilla_backups(vanilla_backups);
+        }
+    }
+
+    Ok(game_folder_changed)
+}
+
+// Puts the winning copy of every file more than one mod has in the game folder
+pub fn redeploy_contested_files(config: &mut Config) -> Result<(), Box<dyn Error>> {
+    let contested_keys = contested_files(config);
+    let contested_paths: BTreeSet<PathBuf> = config.mods
+        .iter()
+        .flat_map(|saved_mod| saved_mod.components().iter().flat_map(ModComponent::files))
+        .map(|installed_file| installed_file.path.clone())
+        .filter(|installed_path| contested_keys.contains(&conflict_key(installed_path)))
+        .collect();
+
+    for contested_path in contested_paths {
+        redeploy_file(config, &contested_path)?;
+    }
+
+    Ok(())
+}
+// What repairing the game folder did, and what it couldn't do
+#[derive(Serialize)]
+pub struct RepairReport {
+    pub redeployed_files: Vec<PathBuf>,         // Game files that were put back as they should be, relative to the game folder
+    pub broken_stored_files: Vec<PathBuf>,      // Files missing from the mod store, or changed after the installation (mods/3/data/pl/pl0000.dtt)
+}
+
+// Rebuilds the game folder from the mod store, going through every file of every installed mod
+pub fn repair_game_folder(config: &mut Config) -> Result<RepairReport, Box<dyn Error>> {
+    let mut report = RepairReport { redeployed_files: vec![], broken_stored_files: vec![] };
+
+    // Files are copied from the store as they are there, so a broken one can only be reported
+    // Its game file is left as it is, instead of getting the broken copy
+    let mut mod_files: BTreeSet<PathBuf> = BTreeSet::new();
+    let mut broken_files: BTreeSet<PathBuf> = BTreeSet::new();
+    for saved_mod in &config.mods {
+        for installed_file in saved_mod.components().iter().flat_map(ModComponent::files) {
+            let stored_file = stored_mod_folder(saved_mod.id()).join(&installed_file.path);
+            if !stored_file.is_file() || file_hash(&stored_file)? != installed_file.hash {
+                report.broken_stored_files.push(stored_file);
+                broken_files.insert(installed_file.path.clone());
+            }
+
+            mod_files.insert(installed_file.path.clone());
+        }
+    }
+    mod_files.retain(|mod_file| !broken_files.contains(mod_file));
+
+    for mod_file in mod_files {
+        if redeploy_file(config, &mod_file)? {
+            report.redeployed_files.push(mod_file);
+        }
+    }
+
+    Ok(report)
+}
+
+pub fn show_repair_report(report: &RepairReport) {
+    if report.redeployed_files.is_empty() {
+        println!("The game folder was already as it should be");
+    } else {
+        println!("{} files put back as they should be:", report.redeployed_files.len());
+        for redeployed_file in &report.redeployed_files {
+            println!("\t{:?}", redeployed_file);
+        }
+    }
+
+    if !report.broken_stored_files.is_empty() {
+        println!("\n{} files are missing from the mod store or were changed, reinstall their mods to get them back:", report.broken_stored_files.len());
+        for broken_stored_file in &report.broken_stored_files {
+            println!("\t{:?}", broken_stored_file);
+        }
+    }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs::{create_dir_all, read_to_string, write};
+
+    use crate::ata_folders::init_test_ata_folders;
+
+    use crate::data_saving::{Mod, ModType};
+
+    use crate::features::{uninstall_mod, ModifiedFiles};
+
+    // Writes the file in the folder, creating the folders it is in
+    fn write_file(folder: &Path, relative_path: &str, contents: &str) {
+        let file_path = folder.join(relative_path);
+        create_dir_all(file_path.parent().unwrap()).unwrap();
+        write(file_path, contents).unwrap();
+    }
+
+    #[test]
+    fn unchanged_original_files_survive_the_mod() {
+        let ata_folders = init_test_ata_folders();
+        let mut config = Config::default();
+        config.game_path = ata_folders.user_data.join("unchanged-original-game");
+        write_file(&config.game_path, "data/pl/pl9000.dat", "original");
+
+        // The mod has the original .dat unchanged, next to the .dtt it changes
+        let mod_id = 9000;
+        let stored_folder = stored_mod_folder(mod_id);
+        write_file(&stored_folder, "data/pl/pl9000.dat", "original");
+        write_file(&stored_folder, "data/pl/pl9000.dtt", "modded");
+        let installed_files = ["data/pl/pl9000.dat", "data/pl/pl9000.dtt"]
+            .map(|installed_file| InstalledFile::from_installed_path(&stored_folder, &stored_folder.join(installed_file)).unwrap());
+        let components = vec![ModComponent::new(ModType::PlayerModels, installed_files.to_vec())];
+        config.add_mod_with_id(Mod::new(String::from("Unchanged original"), None, components, vec![], true), mod_id);
+
+        for installed_file in &installed_files {
+            redeploy_file(&mut config, &installed_file.path).unwrap();
+        }
+        assert_eq!(config.deployed_files, BTreeSet::from([PathBuf::from("data/pl/pl9000.dtt")]));
+        assert_eq!(read_to_string(config.game_path.join("data/pl/pl9000.dtt")).unwrap(), "modded");
+
+        uninstall_mod(&mut config, "Unchanged original", ModifiedFiles::Remove).unwrap();
+        assert_eq!(read_to_string(config.game_path.join("data/pl/pl9000.dat")).unwrap(), "original");
+        assert!(!config.game_path.join("data/pl/pl9000.dtt").exists());
+        assert!(config.deployed_files.is_empty());
+    }
+}
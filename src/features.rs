@@ -1,45 +1,268 @@
+use std::cmp::Reverse;
+
 use std::error::Error;
 
-use std::path::PathBuf;
+use std::fs::{remove_dir, remove_dir_all, remove_file};
+
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use thiserror::Error;
 
-use crate::data_saving::{Mod, ModType};
+use crate::data_saving::{file_hash, format_timestamp, Config, Mod, ModComponent, ModType};
 
 use crate::installation_utilities_and_methods::{
-    check_mod_type, decompress_folder,
+    ask_mod_name, component_destinations, prepare_mod_source,
 };
 use crate::installation_utilities_and_methods::{
-    install_cutscene_replacements, install_player_model, install_reshade_preset, install_texture, install_weapon_model, install_world_model,
+    install_cutscene_replacements, install_model, install_reshade_preset, install_texture,
 };
 
+use crate::deployment::{redeploy_contested_files, redeploy_file};
+
+use crate::mod_conflicts::{conflict_key, contested_files, file_with_key, find_conflicts, winning_mod, ConflictChoice};
+
+use crate::mod_detection::{check_mod_type, find_component_root, game_folder_of, DetectedComponent};
+
+use crate::mod_store::stored_mod_folder;
+
+use crate::output::print_notice;
+
+use crate::vanilla_backups::backup_path;
+
+use crate::user_interactions::{ask_conflict_choices, ask_mod_type_overrides, ask_remove_modified_file, show_detection_report};
+
 
 
 /* -------------------- */
 /*   MOD INSTALLATION   */
 /* -------------------- */
 
-pub fn install_mod(game_path: &PathBuf, compressed_mod_folder_path: PathBuf) -> Result<Mod, Box<dyn Error>> {
+#[derive(Error, Debug)]
+#[allow(clippy::enum_variant_names)]
+pub enum ModManagingError {
+    #[error("{0:?} does not exist")]
+    ModPathMissingError(PathBuf),
+
+    #[error("The given path doesn't contain a mod")]
+    NoModFoundError,
+
+    #[error("\"{0}\" can't be used as name, it is empty, a number (numbers are mod IDs) or another mod already has it")]
+    ModNameUnavailableError(String),
+
+    #[error("There is no installed mod with name or ID \"{0}\"")]
+    ModNotFoundError(String),
+
+    #[error("\"{0}\" is already enabled")]
+    AlreadyEnabledError(String),
+
+    #[error("\"{0}\" is already disabled")]
+    AlreadyDisabledError(String),
+
+    #[error("The installation was skipped, {0:?} is also in another mod")]
+    ConflictingFileError(PathBuf),
+
+    #[error("\"{0}\" can't be moved before or after itself")]
+    SameModError(String),
+}
+impl ModManagingError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ModManagingError::ModPathMissingError(_) => "mod_path_missing",
+            ModManagingError::NoModFoundError => "no_mod_found",
+            ModManagingError::ModNameUnavailableError(_) => "mod_name_unavailable",
+            ModManagingError::ModNotFoundError(_) => "mod_not_found",
+            ModManagingError::AlreadyEnabledError(_) => "already_enabled",
+            ModManagingError::AlreadyDisabledError(_) => "already_disabled",
+            ModManagingError::ConflictingFileError(_) => "conflicting_file",
+            ModManagingError::SameModError(_) => "same_mod",
+        }
+    }
+}
+
+// What the user chose about the mod being installed
+pub enum InstallChoices {
+    // Shows what was found in the mod and asks the user to check the types and name it
+    Ask,
+    // Already chosen (command line), nothing is asked
+    Given {
+        name: String,
+        mod_type: Option<ModType>,      // Type every part of the mod is installed as, instead of the detected ones
+        on_conflict: ConflictChoice,    // What to do with every file installed mods have too
+    },
+}
+
+// The name is used to find the mod later, so it has to be unique, and it can't look like an ID (those are tried first)
+fn is_name_available(config: &Config, mod_name: &str) -> bool {
+    !mod_name.is_empty() && mod_name.parse::<u32>().is_err() && config.find_mod(mod_name).is_none()
+}
+
+// Installs the mod and adds it to the config
+pub fn install_mod<'a>(config: &'a mut Config, mod_path: &Path, choices: InstallChoices) -> Result<&'a Mod, Box<dyn Error>> {
     // Check if it exists
-    if !compressed_mod_folder_path.exists() {
-        return Err("Mod path does not exist".into());
+    if !mod_path.exists() {
+        return Err(ModManagingError::ModPathMissingError(mod_path.to_path_buf()).into());
     }
     
-    // Unzip the mod folder
-    let mut mod_folder_path = decompress_folder(&compressed_mod_folder_path)?;
+    // Unzip the mod folder, or use it directly if it's already a folder
+    // (staging folders are removed once the installation ends, whatever the outcome)
+    let mod_source = prepare_mod_source(mod_path)?;
+    let mod_folder_path = mod_source.path();
     
-    // Get the type of mod containd
-    let mod_data = check_mod_type(&mut mod_folder_path)?
-       	.ok_or("The given path doesn't contain a mod")?;
-    // Install the mod contained in the folder following the correct installation method
-    let installed_mod = match mod_data.0 {
-       	ModType::Textures => install_texture(mod_folder_path, game_path)?,
-       	ModType::PlayerModels => install_player_model(mod_folder_path, game_path)?,
-       	ModType::WeaponModels => install_weapon_model(mod_folder_path, game_path)?,
-       	ModType::WorldModels => install_world_model(mod_folder_path, game_path)?,
-        ModType::CutsceneReplacements => install_cutscene_replacements(mod_folder_path, game_path)?,
-        ModType::ReshadePreset => install_reshade_preset(mod_folder_path, game_path)?,
+    // Get the types of mod contained, each with the files that drove the decision
+    let mut detection_report = check_mod_type(mod_folder_path)?;
+
+    // The type the user gives goes before what ATA found, and covers the files it didn't recognize too
+    match choices {
+        InstallChoices::Ask => {
+            // Let the user check what was found, and fix it, before anything is copied
+            if !detection_report.components.is_empty() || !detection_report.unclassified_files.is_empty() {
+                show_detection_report(&detection_report, mod_folder_path);
+                ask_mod_type_overrides(&mut detection_report)?;
+            }
+        }
+        InstallChoices::Given { mod_type: Some(mod_type), .. } => {
+            for component in detection_report.components.iter_mut() {
+                component.mod_type = mod_type;
+            }
+            detection_report.classify_unclassified_files(mod_type);
+        }
+        InstallChoices::Given { mod_type: None, .. } => {}
+    }
+    if detection_report.components.is_empty() {
+        return Err(ModManagingError::NoModFoundError.into());
+    }
+
+    let on_conflict = match choices {
+        InstallChoices::Ask => None,
+        InstallChoices::Given { on_conflict, .. } => Some(on_conflict),
+    };
+
+    let mod_name = match choices {
+        InstallChoices::Ask => {
+            let mut mod_name = ask_mod_name()?;
+            while !is_name_available(config, &mod_name) {
+                println!("\"{}\" can't be used, it is empty, a number (numbers are mod IDs) or another mod already has it", mod_name);
+                mod_name = ask_mod_name()?;
+            }
+            mod_name
+        }
+        InstallChoices::Given { name, .. } => {
+            let mod_name = name.trim().to_string();
+            if !is_name_available(config, &mod_name) {
+                return Err(ModManagingError::ModNameUnavailableError(mod_name).into());
+            }
+            mod_name
+        }
+    };
+
+    // Files the user gave a type to can change the folder containing the whole component
+    for component in detection_report.components.iter_mut() {
+        component.root = find_component_root(&component.files, mod_folder_path);
+    }
+
+    // Files installed mods have too are reported, and the user chooses which copy goes in the game folder
+    let new_mod_id = config.next_mod_id();
+    let mod_destinations: Vec<PathBuf> = detection_report.components.iter().flat_map(component_destinations).collect();
+    let conflicts = find_conflicts(config, &mod_destinations);
+    let conflict_choices = match on_conflict {
+        None => ask_conflict_choices(&conflicts)?,
+        Some(on_conflict) => {
+            for conflict in &conflicts {
+                print_notice(&format!("{:?} is also in {}", conflict.path, mod_list(&conflict.other_mods)));
+            }
+            vec![on_conflict; conflicts.len()]
+        }
+    };
+
+    let mut conflict_winners: Vec<(PathBuf, u32)> = vec![];
+    for (conflict, conflict_choice) in conflicts.into_iter().zip(conflict_choices) {
+        let winner = match conflict_choice {
+            ConflictChoice::Skip => return Err(ModManagingError::ConflictingFileError(conflict.path).into()),
+            ConflictChoice::Overwrite => new_mod_id,
+            ConflictChoice::Keep => winning_mod(config, &conflict.path, false).unwrap_or(new_mod_id),
+        };
+        conflict_winners.push((conflict_key(&conflict.path), winner));
+    }
+
+    // The mod is installed in the mod store, the game folder only gets copies of the files that win
+    // (a folder left there by an older mod with the same ID is replaced)
+    let stored_files_folder = stored_mod_folder(new_mod_id);
+    if stored_files_folder.exists() {
+        remove_dir_all(&stored_files_folder)?;
+    }
+    let installed_components = match install_components(&detection_report.components, &stored_files_folder) {
+        Ok(installed_components) => installed_components,
+        Err(er) => {
+            let _ = remove_dir_all(&stored_files_folder);
+            return Err(er);
+        }
     };
     
-    Ok(installed_mod)
+    // An absolute path still means something when the mod is looked at from somewhere else
+    let mod_source = mod_path.canonicalize().unwrap_or(mod_path.to_path_buf());
+
+    // Original game files about to be overwritten are backed up while deploying, so uninstalling the mod can bring them back
+    let previous_conflict_winners = config.conflict_winners.clone();
+    config.add_mod(Mod::new(mod_name, Some(mod_source), installed_components, vec![], true));
+    config.conflict_winners.extend(conflict_winners);
+    let deployed_files = mod_files(installed_mod(config, &new_mod_id.to_string())?);
+    for (deployed_index, deployed_file) in deployed_files.iter().enumerate() {
+        if let Err(er) = redeploy_file(config, deployed_file) {
+            config.conflict_winners = previous_conflict_winners;
+            roll_back_installation(config, new_mod_id, &deployed_files[..=deployed_index]);
+            return Err(er);
+        }
+    }
+
+    installed_mod(config, &new_mod_id.to_string()).map_err(Into::into)
+}
+
+// Undoes an installation that failed while deploying the mod, so nothing is left that the config doesn't know about
+// The files already deployed get back what they had before (the original, or the copy of another mod)
+// Errors are ignored, the one that made the installation fail is the one reported
+fn roll_back_installation(config: &mut Config, new_mod_id: u32, deployed_files: &[PathBuf]) {
+    let Some(mod_index) = config.mods.iter().position(|saved_mod| saved_mod.id() == new_mod_id) else {
+        return;
+    };
+    config.mods.remove(mod_index);
+
+    // Its copies are removed, and what was there before is put back, like when a mod is uninstalled
+    for deployed_file in deployed_files {
+        let _ = redeploy_file(config, deployed_file);
+    }
+
+    let _ = remove_dir_all(stored_mod_folder(new_mod_id));
+}
+
+// Install every part of the mod following the correct installation method, all of them make up a single mod
+// The files go in the folder given, laid out as in the game folder
+fn install_components(components: &[DetectedComponent], base_folder: &Path) -> Result<Vec<ModComponent>, Box<dyn Error>> {
+    let mut installed_components: Vec<ModComponent> = vec![];
+    for component in components {
+        let installed_component = match component.mod_type {
+            ModType::Textures => install_texture(component, base_folder)?,
+            ModType::PlayerModels | ModType::WeaponModels | ModType::WorldModels | ModType::EnemyModels
+            | ModType::ObjectModels | ModType::EffectModels | ModType::InterfaceFiles | ModType::ItemModels => {
+                install_model(component, base_folder)?
+            }
+            ModType::CutsceneReplacements => install_cutscene_replacements(component, base_folder)?,
+            ModType::ReshadePreset => install_reshade_preset(component, base_folder)?,
+        };
+        installed_components.push(installed_component);
+    }
+
+    Ok(installed_components)
+}
+
+// "Mod A" (ID: 1), "Mod B" (ID: 4)
+pub fn mod_list(mods: &[(u32, String)]) -> String {
+    mods
+        .iter()
+        .map(|(mod_id, mod_name)| format!("\"{}\" (ID: {})", mod_name, mod_id))
+        .collect::<Vec<String>>()
+        .join(", ")
 }
 
 
@@ -48,15 +271,609 @@ pub fn install_mod(game_path: &PathBuf, compressed_mod_folder_path: PathBuf) ->
 /*   MOD UNINSTALLATION   */
 /* ---------------------- */
 
-pub fn uninstall_mod(game_path: &PathBuf) -> Result<Mod, Box<dyn std::error::Error>> {
-	Ok(Mod::new(String::from("Texture"), vec![], true, ModType::Textures))
+// What to do with the files that changed after the mod was installed
+pub enum ModifiedFiles {
+    Ask,
+    Keep,
+    Remove,
 }
 
+// Removes exactly the files the mod installed, puts back the original game files it replaced, and removes the mod from the config
+// Files other mods have too get the copy of the mod that wins them instead of the original
+// Files changed after the installation are only removed if the user agrees (or chose it beforehand)
+pub fn uninstall_mod(config: &mut Config, mod_id_or_name: &str, modified_files: ModifiedFiles) -> Result<Mod, Box<dyn Error>> {
+    let Some(mod_index) = config.find_mod(mod_id_or_name) else {
+        return Err(ModManagingError::ModNotFoundError(mod_id_or_name.trim().to_string()).into());
+    };
+    let game_path = &config.game_path;
+    let uninstalled_mod = &config.mods[mod_index];
+
+    let mut kept_files: Vec<&Path> = vec![];
+    let mut removed_files: Vec<PathBuf> = vec![];
+    for component in uninstalled_mod.components() {
+        let component_game_folder = component_game_folder(component, game_path);
+
+        for installed_file in component.files() {
+            // Disabled mods, files another mod wins, and textures the mod has twice, don't have their copy in the game folder
+            // and neither do the files that were already in the game as the mod has them (those are originals, left alone)
+            let key = conflict_key(&installed_file.path);
+            if winning_mod(config, &installed_file.path, true) != Some(uninstalled_mod.id())
+                || file_with_key(uninstalled_mod, &key).is_none_or(|winning_file| winning_file.path != installed_file.path)
+                || !config.deployed_files.contains(&installed_file.path)
+            {
+                continue;
+            }
+
+            let installed_path = game_path.join(&installed_file.path);
+            if !installed_path.exists() {
+                print_notice(&format!("{:?} was already removed", installed_file.path));
+                continue;
+            }
 
+            if file_hash(&installed_path)? != installed_file.hash {
+                let remove_modified_file = match modified_files {
+                    ModifiedFiles::Ask => ask_remove_modified_file(&installed_file.path)?,
+                    ModifiedFiles::Keep => false,
+                    ModifiedFiles::Remove => true,
+                };
 
+                if !remove_modified_file {
+                    print_notice(&format!("{:?} was modified after the installation and was kept", installed_file.path));
+                    kept_files.push(&installed_file.path);
+                    continue;
+                }
+            }
+
+            remove_file(&installed_path)?;
+            remove_empty_folders(&installed_path, &component_game_folder);
+            removed_files.push(installed_file.path.clone());
+        }
+    }
+
+    // Kept files stay as they are, nothing is deployed in their place
+    let redeployed_files: Vec<PathBuf> = mod_files(uninstalled_mod)
+        .into_iter()
+        .filter(|installed_file| !kept_files.contains(&installed_file.as_path()))
+        .collect();
+
+    // Kept files belong to the user now
+    let kept_files: Vec<PathBuf> = kept_files.into_iter().map(Path::to_path_buf).collect();
+    let previous_deployed_files = config.deployed_files.clone();
+    for released_file in removed_files.iter().chain(&kept_files) {
+        config.deployed_files.remove(released_file);
+    }
+
+    let previous_conflict_winners = config.conflict_winners.clone();
+    let uninstalled_mod = config.mods.remove(mod_index);
+    config.conflict_winners.retain(|_, winner| *winner != uninstalled_mod.id());
+    for redeployed_file in &redeployed_files {
+        if let Err(er) = redeploy_file(config, redeployed_file) {
+            // The mod goes back in the game folder from the mod store, as if the uninstallation never started
+            // (apart from the files the user chose to keep, which are already theirs)
+            config.mods.insert(mod_index, uninstalled_mod);
+            config.conflict_winners = previous_conflict_winners;
+            config.deployed_files = previous_deployed_files;
+            for redeployed_file in &redeployed_files {
+                let _ = redeploy_file(config, redeployed_file);
+            }
+            return Err(er);
+        }
+    }
 
-pub fn list_mods(mods: &Vec<Mod>) {
-    println!("Not implemented yet, pls do not kill me")
+    // The mod store is only cleaned once the game folder doesn't need the mod anymore
+    let stored_files_folder = stored_mod_folder(uninstalled_mod.id());
+    if stored_files_folder.exists() && let Err(er) = remove_dir_all(&stored_files_folder) {
+        print_notice(&format!("The files of the mod could not be removed from the mod store ({:?}): {}", stored_files_folder, er));
+    }
+
+    Ok(uninstalled_mod)
 }
 
 
+
+/* ------------------------------ */
+/*   ENABLING AND DISABLING MODS   */
+/* ------------------------------ */
+
+// Removes the copies of the files of the mod from the game folder, and puts back the original game files it replaced
+// (or the copies of the other enabled mods that have the same files), the mod stays in the mod store
+pub fn disable_mod<'a>(config: &'a mut Config, mod_id_or_name: &str) -> Result<&'a Mod, Box<dyn Error>> {
+    let Some(mod_index) = config.find_mod(mod_id_or_name) else {
+        return Err(ModManagingError::ModNotFoundError(mod_id_or_name.trim().to_string()).into());
+    };
+    let disabled_mod = &config.mods[mod_index];
+    if !disabled_mod.enabled() {
+        return Err(ModManagingError::AlreadyDisabledError(disabled_mod.name().to_string()).into());
+    }
+
+    set_mod_enabled(config, mod_index, false)?;
+
+    Ok(&config.mods[mod_index])
+}
+
+// Copies the files of the mod from the mod store back to the game folder, backing up the original game files again
+// Files other enabled mods have too only go back if the mod wins them
+pub fn enable_mod<'a>(config: &'a mut Config, mod_id_or_name: &str) -> Result<&'a Mod, Box<dyn Error>> {
+    let Some(mod_index) = config.find_mod(mod_id_or_name) else {
+        return Err(ModManagingError::ModNotFoundError(mod_id_or_name.trim().to_string()).into());
+    };
+    let enabled_mod = &config.mods[mod_index];
+    if enabled_mod.enabled() {
+        return Err(ModManagingError::AlreadyEnabledError(enabled_mod.name().to_string()).into());
+    }
+
+    // The game folder could have changed while the mod was disabled, so the originals are looked for again while redeploying
+    set_mod_enabled(config, mod_index, true)?;
+
+    Ok(&config.mods[mod_index])
+}
+
+// Enables or disables the mod and redeploys its files
+// If one can't be redeployed the mod goes back to how it was, and so do the files already redeployed
+fn set_mod_enabled(config: &mut Config, mod_index: usize, enabled: bool) -> Result<(), Box<dyn Error>> {
+    config.mods[mod_index].set_enabled(enabled);
+
+    let redeployed_files = mod_files(&config.mods[mod_index]);
+    for (redeployed_index, redeployed_file) in redeployed_files.iter().enumerate() {
+        if let Err(er) = redeploy_file(config, redeployed_file) {
+            config.mods[mod_index].set_enabled(!enabled);
+            for redeployed_file in &redeployed_files[..=redeployed_index] {
+                let _ = redeploy_file(config, redeployed_file);
+            }
+            return Err(er);
+        }
+    }
+    config.mods[mod_index].mark_updated();
+
+    Ok(())
+}
+
+
+
+/* ------------------------------ */
+/*   GAME FOLDER MANAGING UTILS   */
+/* ------------------------------ */
+
+// Folder the game expects the files of the component in
+// Folders are cleaned up until this one, which is left even if empty
+fn component_game_folder(component: &ModComponent, game_path: &Path) -> PathBuf {
+    game_folder_of(component.mod_type())
+        .map(|game_folder| game_path.join(game_folder))
+        .unwrap_or(game_path.to_path_buf())
+}
+
+// Files of the mod, relative to the game folder
+fn mod_files(saved_mod: &Mod) -> Vec<PathBuf> {
+    saved_mod.components().iter().flat_map(ModComponent::files).map(|installed_file| installed_file.path.clone()).collect()
+}
+
+// Removes the folders containing the file that were left empty, up until (and excluding) the last folder
+pub fn remove_empty_folders(removed_file: &Path, last_folder: &Path) {
+    for folder in removed_file.ancestors().skip(1) {
+        if folder == last_folder || !folder.starts_with(last_folder) {
+            break;
+        }
+        // Fails if the folder isn't empty, and there's no reason to look further up
+        if remove_dir(folder).is_err() {
+            break;
+        }
+    }
+}
+
+
+
+
+// The installed mod with the given name or ID
+pub fn installed_mod<'a>(config: &'a Config, mod_id_or_name: &str) -> Result<&'a Mod, ModManagingError> {
+    config
+        .find_mod(mod_id_or_name)
+        .map(|mod_index| &config.mods[mod_index])
+        .ok_or(ModManagingError::ModNotFoundError(mod_id_or_name.trim().to_string()))
+}
+
+// Everything ATA knows about a single mod, including what the other mods have to do with it
+#[derive(Serialize)]
+pub struct ModInfo<'a> {
+    #[serde(rename = "mod")]
+    pub shown_mod: &'a Mod,
+    pub vanilla_files: Vec<VanillaFileInfo<'a>>,     // Original game files the mod replaced
+    pub conflicts: Vec<ConflictInfo<'a>>,            // Files other installed mods have too
+}
+
+#[derive(Serialize)]
+pub struct VanillaFileInfo<'a> {
+    pub path: &'a Path,
+    pub backed_up: bool,        // Whether the original is still in the backups folder
+}
+
+#[derive(Serialize)]
+pub struct ConflictInfo<'a> {
+    pub path: &'a Path,
+    pub other_mods: Vec<ModReference<'a>>,
+}
+
+#[derive(Serialize)]
+pub struct ModReference<'a> {
+    pub id: u32,
+    pub name: &'a str,
+}
+
+pub fn mod_info<'a>(config: &'a Config, shown_mod: &'a Mod) -> ModInfo<'a> {
+    let vanilla_files = shown_mod
+        .vanilla_backups()
+        .iter()
+        .map(|vanilla_file| VanillaFileInfo { path: vanilla_file, backed_up: backup_path(vanilla_file).is_file() })
+        .collect();
+
+    let mut conflicts: Vec<ConflictInfo> = vec![];
+    for installed_file in shown_mod.components().iter().flat_map(ModComponent::files) {
+        let other_mods: Vec<ModReference> = config.mods
+            .iter()
+            .filter(|other_mod| other_mod.id() != shown_mod.id())
+            .filter(|other_mod| file_with_key(other_mod, &conflict_key(&installed_file.path)).is_some())
+            .map(|other_mod| ModReference { id: other_mod.id(), name: other_mod.name() })
+            .collect();
+
+        if !other_mods.is_empty() {
+            conflicts.push(ConflictInfo { path: &installed_file.path, other_mods });
+        }
+    }
+
+    ModInfo { shown_mod, vanilla_files, conflicts }
+}
+
+// Prints what ATA knows about a single mod
+pub fn show_mod_info(info: &ModInfo) {
+    let shown_mod = info.shown_mod;
+
+    println!("{} (ID: {}, {})", shown_mod.name(), shown_mod.id(), if shown_mod.enabled() { "enabled" } else { "disabled" });
+    match shown_mod.source() {
+        Some(source) => println!("Installed from: {:?}", source),
+        None => println!("Installed from: unknown"),
+    }
+    println!("Installed: {}", format_timestamp(shown_mod.installed_at()));
+    println!("Last changed: {}", format_timestamp(shown_mod.updated_at()));
+    println!("Size: {} in {} file(s)", format_size(shown_mod.total_size()), shown_mod.file_count());
+
+    println!("\nFILES");
+    for component in shown_mod.components() {
+        println!("\t{:?}, {} file(s)", component.mod_type(), component.files().len());
+        for installed_file in component.files() {
+            println!("\t\t{:?} ({}, SHA-256 {})", installed_file.path, format_size(installed_file.size), installed_file.hash);
+        }
+    }
+
+    println!("\nREPLACED GAME FILES");
+    if info.vanilla_files.is_empty() {
+        println!("\tNone");
+    }
+    for vanilla_file in &info.vanilla_files {
+        let backup_state = if vanilla_file.backed_up { "original backed up" } else { "BACKUP MISSING" };
+        println!("\t{:?} ({})", vanilla_file.path, backup_state);
+    }
+
+    println!("\nCONFLICTS WITH OTHER MODS");
+    if info.conflicts.is_empty() {
+        println!("\tNone");
+    }
+    for conflict in &info.conflicts {
+        let other_mods: Vec<String> = conflict.other_mods
+            .iter()
+            .map(|other_mod| format!("\"{}\" (ID: {})", other_mod.name, other_mod.id))
+            .collect();
+        println!("\t{:?}, also in {}", conflict.path, other_mods.join(", "));
+    }
+}
+
+
+
+/* ------------------- */
+/*   LISTING THE MODS   */
+/* ------------------- */
+
+// Which mods to list, nothing set means all of them
+#[derive(Default)]
+pub struct ModFilter {
+    pub mod_type: Option<ModType>,      // Only mods with a part of this type
+    pub enabled: Option<bool>,          // Only enabled (true) or disabled (false) mods
+    pub name: Option<String>,           // Only mods with this in their name (case doesn't matter)
+}
+
+// Order of the listed mods
+#[derive(clap::ValueEnum, Clone, Copy, Default)]
+pub enum ModSorting {
+    // Order of installation
+    #[default]
+    Id,
+    Name,
+    // Biggest first
+    Size,
+    // Most recent first
+    InstallTime,
+}
+
+// The mods the filter lets through, in the chosen order
+pub fn filter_and_sort_mods<'a>(mods: &'a [Mod], filter: &ModFilter, sorting: ModSorting) -> Vec<&'a Mod> {
+    let name_filter = filter.name.as_ref().map(|name| name.to_lowercase());
+
+    let mut listed_mods: Vec<&Mod> = mods
+        .iter()
+        .filter(|listed_mod| filter.mod_type.is_none_or(|mod_type| listed_mod.mod_types().contains(&mod_type)))
+        .filter(|listed_mod| filter.enabled.is_none_or(|enabled| listed_mod.enabled() == enabled))
+        .filter(|listed_mod| name_filter.as_ref().is_none_or(|name| listed_mod.name().to_lowercase().contains(name)))
+        .collect();
+
+    match sorting {
+        ModSorting::Id => listed_mods.sort_by_key(|listed_mod| listed_mod.id()),
+        ModSorting::Name => listed_mods.sort_by_key(|listed_mod| listed_mod.name().to_lowercase()),
+        ModSorting::Size => listed_mods.sort_by_key(|listed_mod| Reverse(listed_mod.total_size())),
+        ModSorting::InstallTime => listed_mods.sort_by_key(|listed_mod| Reverse(listed_mod.installed_at())),
+    }
+
+    listed_mods
+}
+
+// Prints the mods as a table, one row each
+pub fn list_mods(mods: &[&Mod]) {
+    if mods.is_empty() {
+        println!("No mods to show");
+        return;
+    }
+
+    let header = ["ID", "NAME", "TYPE", "STATE", "FILES", "SIZE", "INSTALLED"];
+    let rows: Vec<[String; 7]> = mods
+        .iter()
+        .map(|listed_mod| [
+            listed_mod.id().to_string(),
+            listed_mod.name().to_string(),
+            listed_mod.mod_types().iter().map(|mod_type| format!("{:?}", mod_type)).collect::<Vec<String>>().join(", "),
+            String::from(if listed_mod.enabled() { "enabled" } else { "disabled" }),
+            listed_mod.file_count().to_string(),
+            format_size(listed_mod.total_size()),
+            format_timestamp(listed_mod.installed_at()),
+        ])
+        .collect();
+
+    print_table(header, &rows);
+}
+
+// Prints the rows under the header, every column as wide as its longest cell
+fn print_table<const COLUMNS: usize>(header: [&str; COLUMNS], rows: &[[String; COLUMNS]]) {
+    let header = header.map(String::from);
+
+    let mut column_widths = header.clone().map(|cell| cell.chars().count());
+    for row in rows {
+        for (column_width, cell) in column_widths.iter_mut().zip(row) {
+            *column_width = (*column_width).max(cell.chars().count());
+        }
+    }
+
+    for row in [header].iter().chain(rows) {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(column_widths)
+            .map(|(cell, column_width)| format!("{:<width$}", cell, width = column_width))
+            .collect();
+        println!("{}", cells.join("  ").trim_end());
+    }
+}
+
+// Size in bytes made readable (1.5 MiB)
+pub fn format_size(size: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if size < 1024 {
+        return format!("{} B", size);
+    }
+
+    let mut scaled_size = size as f64 / 1024.0;
+    let mut unit_index = 0;
+    while scaled_size >= 1024.0 && unit_index < UNITS.len() - 1 {
+        scaled_size /= 1024.0;
+        unit_index += 1;
+    }
+    format!("{:.1} {}", scaled_size, UNITS[unit_index])
+}
+
+
+
+
+
+/* -------------- */
+/*   LOAD ORDER   */
+/* -------------- */
+
+// The load order is the order of the mods in the config, from the lowest to the highest priority
+// A mod later in the order wins the files it shares with the earlier ones, unless a winner was chosen for that file when installing
+
+// Where to move a mod in the load order, next to another mod (name or ID)
+pub enum OrderPlace {
+    Before(String),
+    After(String),
+}
+
+// Moves the mod in the load order, then puts in the game folder the copies of the files that win with the new order
+// The winners chosen when installing for the files of the moved mod are forgotten, the new order decides them
+pub fn move_mod<'a>(config: &'a mut Config, mod_id_or_name: &str, place: &OrderPlace) -> Result<&'a Mod, Box<dyn Error>> {
+    let (other_id_or_name, after) = match place {
+        OrderPlace::Before(other_id_or_name) => (other_id_or_name, false),
+        OrderPlace::After(other_id_or_name) => (other_id_or_name, true),
+    };
+    let mod_index = installed_mod_index(config, mod_id_or_name)?;
+    let other_index = installed_mod_index(config, other_id_or_name)?;
+    if mod_index == other_index {
+        return Err(ModManagingError::SameModError(config.mods[mod_index].name().to_string()).into());
+    }
+
+    let other_id = config.mods[other_index].id();
+    let moved_mod = config.mods.remove(mod_index);
+    let new_index = config.mods.iter().position(|saved_mod| saved_mod.id() == other_id).unwrap_or(config.mods.len()) + after as usize;
+
+    config.conflict_winners.retain(|contested_file, _| file_with_key(&moved_mod, contested_file).is_none());
+    config.mods.insert(new_index, moved_mod);
+    redeploy_contested_files(config)?;
+    config.mods[new_index].mark_updated();
+
+    Ok(&config.mods[new_index])
+}
+
+fn installed_mod_index(config: &Config, mod_id_or_name: &str) -> Result<usize, ModManagingError> {
+    config.find_mod(mod_id_or_name).ok_or(ModManagingError::ModNotFoundError(mod_id_or_name.trim().to_string()))
+}
+
+// A mod in the load order, with how many of the files it shares with other mods it wins
+#[derive(Serialize)]
+pub struct LoadOrderEntry<'a> {
+    pub position: usize,            // 1 is the lowest priority
+    pub id: u32,
+    pub name: &'a str,
+    pub enabled: bool,
+    pub shared_files: usize,        // Files other installed mods have too
+    pub won_files: usize,           // Shared files whose copy of this mod goes in the game folder (while it's enabled)
+}
+
+pub fn load_order(config: &Config) -> Vec<LoadOrderEntry<'_>> {
+    let contested_files = contested_files(config);
+
+    config.mods
+        .iter()
+        .enumerate()
+        .map(|(mod_index, saved_mod)| {
+            let shared_files: Vec<&PathBuf> = contested_files.iter().filter(|contested_file| file_with_key(saved_mod, contested_file).is_some()).collect();
+            let won_files = shared_files
+                .iter()
+                .filter(|shared_file| winning_mod(config, shared_file, false) == Some(saved_mod.id()))
+                .count();
+
+            LoadOrderEntry {
+                position: mod_index + 1,
+                id: saved_mod.id(),
+                name: saved_mod.name(),
+                enabled: saved_mod.enabled(),
+                shared_files: shared_files.len(),
+                won_files,
+            }
+        })
+        .collect()
+}
+
+// Prints the load order as a table, from the lowest to the highest priority
+pub fn show_load_order(load_order: &[LoadOrderEntry]) {
+    if load_order.is_empty() {
+        println!("No mods to show");
+        return;
+    }
+
+    let header = ["POSITION", "ID", "NAME", "STATE", "SHARED FILES WON"];
+    let rows: Vec<[String; 5]> = load_order
+        .iter()
+        .map(|entry| [
+            entry.position.to_string(),
+            entry.id.to_string(),
+            entry.name.to_string(),
+            String::from(if entry.enabled { "enabled" } else { "disabled" }),
+            format!("{}/{}", entry.won_files, entry.shared_files),
+        ])
+        .collect();
+
+    print_table(header, &rows);
+    println!("(mods further down win the files they share with the ones above)");
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::ata_folders::init_test_ata_folders;
+
+    use std::fs::{create_dir_all, read_to_string, write};
+
+    use crate::data_saving::InstalledFile;
+
+    // Config with a game folder of its own, named after the test
+    fn test_config(game_folder_name: &str) -> Config {
+        let ata_folders = init_test_ata_folders();
+        let mut config = Config::default();
+        config.game_path = ata_folders.user_data.join(game_folder_name);
+        config
+    }
+
+    // Adds a mod whose files are in the mod store with their path as contents, followed by the ID of the mod
+    fn add_stored_mod(config: &mut Config, mod_id: u32, game_relative_paths: &[&str], enabled: bool) {
+        let stored_folder = stored_mod_folder(mod_id);
+        let installed_files: Vec<InstalledFile> = game_relative_paths
+            .iter()
+            .map(|game_relative_path| {
+                let stored_file = stored_folder.join(game_relative_path);
+                create_dir_all(stored_file.parent().unwrap()).unwrap();
+                write(&stored_file, format!("{} {}", game_relative_path, mod_id)).unwrap();
+                InstalledFile::from_installed_path(&stored_folder, &stored_file).unwrap()
+            })
+            .collect();
+        let components = vec![ModComponent::new(ModType::Textures, installed_files)];
+        config.add_mod_with_id(Mod::new(format!("Mod {}", mod_id), None, components, vec![], enabled), mod_id);
+    }
+
+    // Makes copying a file at the path fail, with a folder that isn't empty in its place
+    fn block_game_path(config: &Config, game_relative_path: &str) {
+        let blocking_folder = config.game_path.join(game_relative_path);
+        create_dir_all(&blocking_folder).unwrap();
+        write(blocking_folder.join("blocking"), "").unwrap();
+    }
+
+    #[test]
+    fn moving_a_mod_changes_the_winner() {
+        let mut config = test_config("load-order-game");
+
+        let shared_file = Path::new("data/pl/pl9200.dtt");
+        for mod_id in [9200, 9201, 9202] {
+            let installed_file = InstalledFile { path: shared_file.to_path_buf(), size: 0, hash: format!("hash of {}", mod_id) };
+            let components = vec![ModComponent::new(ModType::PlayerModels, vec![installed_file])];
+            config.add_mod_with_id(Mod::new(format!("Mod {}", mod_id), None, components, vec![], true), mod_id);
+        }
+        assert_eq!(winning_mod(&config, shared_file, true), Some(9202));
+
+        move_mod(&mut config, "9200", &OrderPlace::After(String::from("9202"))).unwrap();
+        assert_eq!(winning_mod(&config, shared_file, true), Some(9200));
+
+        move_mod(&mut config, "9200", &OrderPlace::Before(String::from("9201"))).unwrap();
+        assert_eq!(winning_mod(&config, shared_file, true), Some(9202));
+
+        // The winner chosen when installing is forgotten once one of the mods having the file is moved
+        config.conflict_winners.insert(shared_file.to_path_buf(), 9201);
+        assert_eq!(winning_mod(&config, shared_file, true), Some(9201));
+        move_mod(&mut config, "9200", &OrderPlace::Before(String::from("9201"))).unwrap();
+        assert_eq!(winning_mod(&config, shared_file, true), Some(9202));
+    }
+
+    #[test]
+    fn failed_enabling_is_rolled_back() {
+        let mut config = test_config("failed-enabling-game");
+        add_stored_mod(&mut config, 9300, &["SK_Res/inject/textures/9300a.dds", "SK_Res/inject/textures/9300b.dds"], false);
+        block_game_path(&config, "SK_Res/inject/textures/9300b.dds");
+
+        assert!(enable_mod(&mut config, "9300").is_err());
+        assert!(!config.mods[0].enabled());
+        assert!(!config.game_path.join("SK_Res/inject/textures/9300a.dds").exists());
+        assert!(config.deployed_files.is_empty());
+    }
+
+    #[test]
+    fn failed_uninstallation_is_rolled_back() {
+        let mut config = test_config("failed-uninstallation-game");
+        add_stored_mod(&mut config, 9310, &["SK_Res/inject/textures/a/9310.dds"], true);
+        add_stored_mod(&mut config, 9311, &["SK_Res/inject/textures/b/9310.dds"], true);
+        redeploy_file(&mut config, Path::new("SK_Res/inject/textures/b/9310.dds")).unwrap();
+
+        // The copy of the other mod can't take the place of the uninstalled one
+        block_game_path(&config, "SK_Res/inject/textures/a/9310.dds");
+        assert!(uninstall_mod(&mut config, "9311", ModifiedFiles::Remove).is_err());
+
+        assert!(config.find_mod("9311").is_some());
+        assert!(stored_mod_folder(9311).exists());
+        let game_file = config.game_path.join("SK_Res/inject/textures/b/9310.dds");
+        assert_eq!(read_to_string(game_file).unwrap(), "SK_Res/inject/textures/b/9310.dds 9311");
+        assert!(config.deployed_files.contains(Path::new("SK_Res/inject/textures/b/9310.dds")));
+    }
+}
@@ -1,18 +1,29 @@
 use std::error::Error;
 
-use std::fs::{copy, read_dir, File};
+use std::fs::{copy, create_dir_all, read_link, remove_dir_all, File};
 
-use std::io::{stdin, stdout, Write};
+use std::io::{self, stdin, stdout, Read, Write};
 
-use std::path::{PathBuf, Path};
+use std::path::{Component, PathBuf, Path};
+
+use std::process;
+
+use sevenz_rust2::{ArchiveEntry, ArchiveReader, Password};
 
 use thiserror::Error;
 
+#[cfg(feature = "rar")]
+use unrar::{error::Code, Archive, VolumeInfo};
+
 use walkdir::WalkDir;
 
 use zip::ZipArchive;
 
-use crate::data_saving::{Mod, ModType};
+use crate::ata_folders::ata_folders;
+
+use crate::data_saving::{InstalledFile, ModComponent, ModType};
+
+use crate::mod_detection::{component_file_paths, data_folder_of, game_folder_of, DetectedComponent};
 
 
 
@@ -21,234 +32,554 @@ use crate::data_saving::{Mod, ModType};
 /* ------------- */
 
 #[derive(Error, Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum InstallationError {
-    #[error("Failed to access file: {0}")]
-    FileAcessingError(String),
-
-    #[error("Could not read file extension")]
-    ExtensionReadingError,
-
     #[error("Could not read filename")]
     FilenameReadingError,
 
-    #[error("Failed to decompress folder")]
-    FolderDecompressionError,
+    #[error("Archive entry \"{0}\" contains \"..\" and would be extracted outside of the staging folder")]
+    ParentFolderEntryError(String),
 
-    #[error("Failed to access the console")]
-    ConsoleAccessingError,
+    #[error("Archive entry \"{0}\" has an absolute path and would be extracted outside of the staging folder")]
+    AbsolutePathEntryError(String),
 
-    #[error("Error occurred while copying files")]
-    CopyingFilesError,
-}
+    #[error("Archive entry \"{entry}\" is a symlink pointing outside of the staging folder (to \"{target}\")")]
+    EscapingSymlinkEntryError { entry: String, target: String },
 
+    #[cfg(feature = "rar")]
+    #[error("Archive entry \"{0}\" is a symlink with a target too long to be a real path")]
+    OversizedSymlinkEntryError(String),
 
+    #[error("The archive expands to more than {} GiB, it is refused to protect your disk", .0 / (1024 * 1024 * 1024))]
+    ExtractedSizeLimitError(u64),
 
-pub fn check_mod_type(mod_folder_path: &mut PathBuf) -> Result<Option<(ModType, PathBuf)>, Box<dyn Error>> {
-    // Define variables that will be returned
-    let mut mod_files_path: Option<PathBuf> = None;
-    let mut mod_contained: Option<ModType> = None;
-    
-    // Start looking at the contents of mod folder
-    for entry in WalkDir::new(&mod_folder_path) {
-        let current_entry = entry?;
-        let entry_path = current_entry.path();
-        
-        // Skip folders
-        if !current_entry.file_type().is_file() {
-           	continue
-        }         
-        // Get current entry file extension
-        let extension = match get_file_extension(entry_path) {
-            Ok(ext) => ext,
-            Err(err) => {
-                eprintln!("{}", err);
-                continue;
-            }
-        };
+    #[error("The archive contains more than {0} files, it is refused to protect your disk")]
+    ExtractedFilesLimitError(usize),
 
-        // For each valid entry check if it is the file of a mod
-        mod_contained = match extension {
-            "dss" => Some(ModType::Textures),
-            "dtt" | "dat" => {
-                let Some(name) = entry_path.file_name() else {
-                    println!("\"{:?}\" is a path that ends in .. (parent directory) or . (current directory), and will therefore be skipped", entry_path);
-                    continue;
-                };
-                match name.to_str() {
-                    Some("pl") => Some(ModType::PlayerModels),
-                    Some("wp") => Some(ModType::WeaponModels),
-                    Some("bg") => Some(ModType::WorldModels),
-                    Some(_) => None,
-                    None => {
-                        println!("\"{:?}\" contains invalid Unicode in its name and will therefore will be skipped", entry_path);
-                        continue;
-                    }
-                }
-            }  // RESHADE
-            "usm" => Some(ModType::CutsceneReplacements),
-            _ => None,
-        };
+    #[error("\"{0}\" is not a supported archive format")]
+    UnsupportedArchiveError(String),
+
+    #[cfg(feature = "rar")]
+    #[error("Multi-volume RAR archives (.part1.rar, .r00...) are not supported, join them in a single archive first")]
+    MultiVolumeRarError,
 
-        if mod_contained.is_some() {
-            // Update mod_files_path
-            mod_files_path = Some(entry_path.to_path_buf());
-            break;
+    #[cfg(feature = "rar")]
+    #[error("Encrypted archives are not supported, extract it with its password first")]
+    EncryptedArchiveError,
+
+    #[cfg(not(feature = "rar"))]
+    #[error("This build of ATA was compiled without RAR support (cargo feature \"rar\")")]
+    RarSupportDisabledError,
+
+    #[error("\"{0}\" is neither an archive, a folder or a mod file (.dds, .dat, .dtt, .usm)")]
+    UnrecognizedModSourceError(String),
+}
+impl InstallationError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            InstallationError::FilenameReadingError => "filename_unreadable",
+            InstallationError::ParentFolderEntryError(_) => "entry_outside_staging",
+            InstallationError::AbsolutePathEntryError(_) => "entry_absolute_path",
+            InstallationError::EscapingSymlinkEntryError { .. } => "entry_escaping_symlink",
+            #[cfg(feature = "rar")]
+            InstallationError::OversizedSymlinkEntryError(_) => "entry_oversized_symlink",
+            InstallationError::ExtractedSizeLimitError(_) => "archive_too_large",
+            InstallationError::ExtractedFilesLimitError(_) => "archive_too_many_files",
+            InstallationError::UnsupportedArchiveError(_) => "unsupported_archive",
+            #[cfg(feature = "rar")]
+            InstallationError::MultiVolumeRarError => "multi_volume_rar",
+            #[cfg(feature = "rar")]
+            InstallationError::EncryptedArchiveError => "encrypted_archive",
+            #[cfg(not(feature = "rar"))]
+            InstallationError::RarSupportDisabledError => "rar_support_disabled",
+            InstallationError::UnrecognizedModSourceError(_) => "unrecognized_mod_source",
         }
     }
-    
-    Ok(mod_contained.zip(mod_files_path))
 }
-    
-pub fn decompress_folder(zipped_mod_folder: &PathBuf) -> Result<PathBuf, Box<dyn Error>> {
-    let mod_file = File::open(zipped_mod_folder)?;
 
-    Ok(PathBuf::new())
+
+
+// Archive formats ATA can extract, recognized by their magic bytes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArchiveFormat {
+    Zip,
+    SevenZip,
+    Rar4,
+    Rar5,
 }
+impl ArchiveFormat {
+    // Reads the first bytes of the file and compares them with the known signatures
+    pub fn detect(path: &Path) -> Result<Option<Self>, io::Error> {
+        let mut magic_bytes = [0u8; 8];
+        let bytes_read = File::open(path)?.read(&mut magic_bytes)?;
+        let magic_bytes = &magic_bytes[..bytes_read];
+
+        let format = if magic_bytes.starts_with(b"PK\x03\x04") || magic_bytes.starts_with(b"PK\x05\x06") {
+            Some(Self::Zip)
+        } else if magic_bytes.starts_with(b"7z\xBC\xAF\x27\x1C") {
+            Some(Self::SevenZip)
+        } else if magic_bytes.starts_with(b"Rar!\x1A\x07\x01\x00") {
+            Some(Self::Rar5)
+        } else if magic_bytes.starts_with(b"Rar!\x1A\x07\x00") {
+            Some(Self::Rar4)
+        } else {
+            None
+        };
 
-fn decompress_zip() {
+        Ok(format)
+    }
+}
 
+// Folder inside ATA's data folder where a mod gets extracted, it is deleted when it goes out of scope
+pub struct StagingFolder {
+    path: PathBuf,
 }
-fn decompress_7z() {
+impl StagingFolder {
+    // Creates an empty staging folder named after the archive (or file) that will be put in it
+    fn create(archive_path: &Path) -> Result<Self, io::Error> {
+        let archive_name = archive_path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or(String::from("mod"));
+
+        let path = ata_folders().cache
+            .join("staging")
+            .join(format!("{}-{}", archive_name, process::id()));
+
+        // Leftovers of a previous run that got interrupted
+        if path.exists() {
+            remove_dir_all(&path)?;
+        }
+        create_dir_all(&path)?;
 
+        Ok(Self { path })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+impl Drop for StagingFolder {
+    fn drop(&mut self) {
+        if let Err(er) = remove_dir_all(&self.path) {
+            eprintln!("Warning: Could not remove the staging folder {:?}. {}", self.path, er);
+        }
+    }
 }
-fn decompress_rar() {
 
+
+
+// Where the files of the mod being installed are read from
+pub enum ModSource {
+    Staged(StagingFolder),      // Archive extracted, or loose file copied, in a staging folder
+    Folder(PathBuf),            // Folder the user already extracted (or made) by hand, used as it is
+}
+impl ModSource {
+    pub fn path(&self) -> &Path {
+        match self {
+            ModSource::Staged(staging_folder) => staging_folder.path(),
+            ModSource::Folder(folder_path) => folder_path,
+        }
+    }
 }
 
-fn get_file_extension(path: &Path) -> Result<&str, String> {
-    let Some(extension) = path.extension() else {
-        return Err(String::from(format!("{:?} is an extensionless file", path)));
-    };
-    let Some(extension_str) = extension.to_str() else {
-       	return Err(String::from(format!("{:?} contains invalid UTF-8 in its extension", path)));
+// Turns whatever the user gave (archive, folder or single mod file) into a folder containing the mod
+pub fn prepare_mod_source(mod_path: &Path) -> Result<ModSource, Box<dyn Error>> {
+    if mod_path.is_dir() {
+        return Ok(ModSource::Folder(mod_path.to_path_buf()));
+    }
+
+    if ArchiveFormat::detect(mod_path)?.is_some() {
+        return Ok(ModSource::Staged(decompress_folder(mod_path)?));
+    }
+
+    // A single file is put alone in a staging folder, so it goes through the same steps as archives
+    let is_loose_mod_file = get_file_extension(mod_path)
+        .is_ok_and(|extension| LOOSE_MOD_FILE_EXTENSIONS.contains(&extension.to_lowercase().as_str()));
+    if is_loose_mod_file {
+        let Some(file_name) = mod_path.file_name() else {
+            return Err(InstallationError::FilenameReadingError.into());
+        };
+
+        let staging_folder = StagingFolder::create(mod_path)?;
+        copy(mod_path, staging_folder.path().join(file_name))?;
+
+        return Ok(ModSource::Staged(staging_folder));
+    }
+
+    Err(InstallationError::UnrecognizedModSourceError(mod_path.display().to_string()).into())
+}
+
+// Files that can be installed by themselves, without being in an archive or folder
+const LOOSE_MOD_FILE_EXTENSIONS: [&str; 4] = ["dds", "dat", "dtt", "usm"];
+
+// Extracts the archive in a new staging folder, which gets removed if the extraction fails
+// The decompressor is chosen from the magic bytes, since downloaded mods often have the wrong extension
+pub fn decompress_folder(zipped_mod_folder: &Path) -> Result<StagingFolder, Box<dyn Error>> {
+    let Some(archive_format) = ArchiveFormat::detect(zipped_mod_folder)? else {
+        return Err(InstallationError::UnsupportedArchiveError(zipped_mod_folder.display().to_string()).into());
     };
+
+    let staging_folder = StagingFolder::create(zipped_mod_folder)?;
+    let mut extraction_guard = ExtractionGuard::new(staging_folder.path());
+    match archive_format {
+        ArchiveFormat::Zip => decompress_zip(File::open(zipped_mod_folder)?, &mut extraction_guard)?,
+        ArchiveFormat::SevenZip => decompress_7z(File::open(zipped_mod_folder)?, &mut extraction_guard)?,
+        ArchiveFormat::Rar4 | ArchiveFormat::Rar5 => decompress_rar(zipped_mod_folder, &mut extraction_guard)?,
+    }
     
-    Ok(extension_str)
+    // Last line of defense, in case a decompressor created a link by itself
+    check_staged_symlinks(staging_folder.path())?;
+
+    Ok(staging_folder)
 }
 
+fn decompress_zip(mod_file: File, extraction_guard: &mut ExtractionGuard) -> Result<(), Box<dyn Error>> {
+    let mut archive = ZipArchive::new(mod_file)?;
 
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index)?;
+        let entry_name = entry.name().to_string();
 
-/* ------------------------ */
-/*   INSTALLATION METHODS   */
-/* ------------------------ */
+        if entry.is_symlink() {
+            let link_target = read_symlink_target(&mut entry)?;
+            extraction_guard.check_symlink(&entry_name, &link_target)?;
+        }
+        else if entry.is_dir() {
+            extraction_guard.create_folder(&entry_name)?;
+        }
+        else {
+            extraction_guard.write_file(&entry_name, &mut entry)?;
+        }
+    }
 
-pub fn install_texture(dss_folder_path: PathBuf, game_path: &PathBuf) -> Result<Mod, Box<dyn Error>> {
-    let answer = ask_mod_name()?;
+    Ok(())
+}
+fn decompress_7z(mod_file: File, extraction_guard: &mut ExtractionGuard) -> Result<(), Box<dyn Error>> {
+    // Solid archives are decoded one block at a time, so entries are written in the order they come out
+    let mut archive = ArchiveReader::new(mod_file, Password::empty())?;
+
+    // Errors of ATA can't go through the closure, so they are kept here and returned afterwards
+    let mut entry_error: Option<Box<dyn Error>> = None;
+    let extraction_result = archive.for_each_entries(|entry, reader| {
+        match extract_7z_entry(entry, reader, extraction_guard) {
+            Ok(()) => Ok(true),
+            Err(er) => {
+                let message = er.to_string();
+                entry_error = Some(er);
+                Err(sevenz_rust2::Error::Other(message.into()))
+            }
+        }
+    });
 
-    let texture_mods_folder = game_path.join("/SK_Res/inject/textures");
+    if let Some(er) = entry_error {
+        return Err(er);
+    }
+    extraction_result?;
 
-    let mut mod_files: Vec<PathBuf> = vec![];
-    for entry in read_dir(dss_folder_path)? {
-        let current_entry = entry?;
-        let entry_path = current_entry.path();
+    Ok(())
+}
+fn extract_7z_entry(entry: &ArchiveEntry, reader: &mut dyn Read, extraction_guard: &mut ExtractionGuard) -> Result<(), Box<dyn Error>> {
+    // 7-Zip keeps the unix mode in the upper half of the attributes, windows links are reparse points
+    let attributes = entry.windows_attributes();
+    let is_unix_symlink = attributes & 0x8000 != 0 && (attributes >> 16) & 0o170000 == 0o120000;
+    let is_reparse_point = attributes & 0x400 != 0;
+
+    if is_unix_symlink || is_reparse_point {
+        let link_target = read_symlink_target(reader)?;
+        extraction_guard.check_symlink(entry.name(), &link_target)?;
+    }
+    else if entry.is_directory() {
+        extraction_guard.create_folder(entry.name())?;
+    }
+    else {
+        extraction_guard.write_file(entry.name(), reader)?;
+    }
+
+    Ok(())
+}
+#[cfg(feature = "rar")]
+fn decompress_rar(archive_path: &Path, extraction_guard: &mut ExtractionGuard) -> Result<(), Box<dyn Error>> {
+    // Handles both RAR4 and RAR5, the UnRAR library recognizes the version by itself
+    let mut archive = Archive::new(archive_path)
+        .open_for_processing()
+        .map_err(|er| -> Box<dyn Error> {
+            match er.code {
+                Code::MissingPassword | Code::BadPassword => InstallationError::EncryptedArchiveError.into(),
+                _ => er.into(),
+            }
+        })?;
+
+    if archive.volume_info() != VolumeInfo::None {
+        return Err(InstallationError::MultiVolumeRarError.into());
+    }
+    if archive.has_encrypted_headers() {
+        return Err(InstallationError::EncryptedArchiveError.into());
+    }
+
+    while let Some(header) = archive.read_header()? {
+        let entry = header.entry();
+        if entry.is_encrypted() {
+            return Err(InstallationError::EncryptedArchiveError.into());
+        }
+        if entry.is_split() {
+            return Err(InstallationError::MultiVolumeRarError.into());
+        }
 
-        copy(&entry_path, &texture_mods_folder)?;
+        let entry_name = entry.filename.to_string_lossy().into_owned();
+        let is_unix_symlink = entry.file_attr & 0o170000 == 0o120000;
 
-        mod_files.push(entry_path);
+        // UnRAR only ever gets told to write at paths that have already been checked
+        archive = if is_unix_symlink {
+            // UnRAR reads the whole target in memory, so its size is checked before
+            extraction_guard.check_declared_size(entry.unpacked_size)?;
+            if entry.unpacked_size > MAX_SYMLINK_TARGET_LENGTH {
+                return Err(InstallationError::OversizedSymlinkEntryError(entry_name).into());
+            }
+            let (link_target, next_archive) = header.read()?;
+            extraction_guard.check_symlink(&entry_name, &String::from_utf8_lossy(&link_target))?;
+            next_archive
+        }
+        else if entry.is_directory() {
+            extraction_guard.create_folder(&entry_name)?;
+            header.skip()?
+        }
+        else {
+            extraction_guard.check_declared_size(entry.unpacked_size)?;
+            let output_path = extraction_guard.prepare_file(&entry_name)?;
+            let next_archive = header.extract_to(&output_path)?;
+            extraction_guard.count_extracted_size(std::fs::metadata(&output_path)?.len())?;
+            next_archive
+        };
     }
 
-    Ok(Mod::new(
-        answer,
-        mod_files,
-        true,
-        ModType::Textures,
-    ))
+    Ok(())
+}
+#[cfg(not(feature = "rar"))]
+fn decompress_rar(_archive_path: &Path, _extraction_guard: &mut ExtractionGuard) -> Result<(), Box<dyn Error>> {
+    Err(InstallationError::RarSupportDisabledError.into())
 }
 
-pub fn install_player_model(dtt_dat_folder_path: PathBuf, game_path: &PathBuf) -> Result<Mod, Box<dyn std::error::Error>>  {
-    let answer = ask_mod_name()?;
+// Link targets are short paths, anything longer than this isn't a real link
+const MAX_SYMLINK_TARGET_LENGTH: u64 = 4096;
 
-    let pl_mods_folder = game_path.join("/data/pl");
+fn read_symlink_target(reader: &mut dyn Read) -> Result<String, io::Error> {
+    let mut link_target = String::new();
+    reader.take(MAX_SYMLINK_TARGET_LENGTH).read_to_string(&mut link_target)?;
+    Ok(link_target)
+}
 
-    let mut mod_files: Vec<PathBuf> = vec![];
-    for entry in read_dir(dtt_dat_folder_path)? {
-        let current_entry = entry?;
-        let entry_path = current_entry.path();
+// Limits of what a single archive can extract, mod archives are big but never this big
+const MAX_EXTRACTED_SIZE: u64 = 16 * 1024 * 1024 * 1024;
+const MAX_EXTRACTED_FILES: usize = 100_000;
 
-        copy(&entry_path, &pl_mods_folder)?;
+// Writes the entries of an archive in the staging folder, refusing the ones that could harm the system:
+// paths going outside of the staging folder, symlinks pointing outside of it and archive bombs
+struct ExtractionGuard<'a> {
+    staging_folder: &'a Path,
+    extracted_size: u64,
+    extracted_files: usize,
+}
+impl<'a> ExtractionGuard<'a> {
+    fn new(staging_folder: &'a Path) -> Self {
+        Self {
+            staging_folder,
+            extracted_size: 0,
+            extracted_files: 0,
+        }
+    }
 
-        mod_files.push(entry_path);
+    fn create_folder(&mut self, entry_name: &str) -> Result<(), Box<dyn Error>> {
+        let output_path = self.staging_folder.join(relative_entry_path(entry_name)?);
+        self.count_entry()?;
+        create_dir_all(output_path)?;
+        Ok(())
     }
 
-    Ok(Mod::new(
-        answer,
-        mod_files,
-        true,
-        ModType::PlayerModels,
-    ))
-}
+    // The size declared in the archive can be a lie, so the bytes actually written are counted instead
+    fn write_file(&mut self, entry_name: &str, reader: &mut dyn Read) -> Result<(), Box<dyn Error>> {
+        let output_path = self.prepare_file(entry_name)?;
+        let mut output_file = File::create(&output_path)?;
 
-pub fn install_weapon_model(dtt_dat_folder_path: PathBuf, game_path: &PathBuf) -> Result<Mod, Box<dyn std::error::Error>> {
-    let answer = ask_mod_name()?;
+        let remaining_size = MAX_EXTRACTED_SIZE - self.extracted_size;
+        let written_size = io::copy(&mut reader.take(remaining_size + 1), &mut output_file)?;
+        self.count_extracted_size(written_size)?;
 
-    let wp_mods_folder = game_path.join("/data/wp");
+        Ok(())
+    }
 
-    let mut mod_files: Vec<PathBuf> = vec![];
-    for entry in read_dir(dtt_dat_folder_path)? {
-        let current_entry = entry?;
-        let entry_path = current_entry.path();
+    // Checks the path of a file entry and counts it, returning where it has to be written
+    fn prepare_file(&mut self, entry_name: &str) -> Result<PathBuf, Box<dyn Error>> {
+        let output_path = self.staging_folder.join(relative_entry_path(entry_name)?);
+        self.count_entry()?;
+
+        if let Some(parent_folder) = output_path.parent() {
+            create_dir_all(parent_folder)?;
+        }
+
+        Ok(output_path)
+    }
 
-        copy(&entry_path, &wp_mods_folder)?;
+    // Every entry counts, folders and skipped links too, or archives made only of them could be as big as they want
+    fn count_entry(&mut self) -> Result<(), InstallationError> {
+        self.extracted_files += 1;
+        if self.extracted_files > MAX_EXTRACTED_FILES {
+            return Err(InstallationError::ExtractedFilesLimitError(MAX_EXTRACTED_FILES));
+        }
+        Ok(())
+    }
 
-        mod_files.push(entry_path);
+    fn count_extracted_size(&mut self, written_size: u64) -> Result<(), InstallationError> {
+        self.extracted_size += written_size;
+        if self.extracted_size > MAX_EXTRACTED_SIZE {
+            return Err(InstallationError::ExtractedSizeLimitError(MAX_EXTRACTED_SIZE));
+        }
+        Ok(())
     }
 
-    Ok(Mod::new(
-        answer,
-        mod_files,
-        true,
-        ModType::WeaponModels,
-    ))
+    // Refuses early entries that are declared bigger than what's left, before decompressing them
+    #[cfg(feature = "rar")]
+    fn check_declared_size(&self, declared_size: u64) -> Result<(), InstallationError> {
+        if declared_size > MAX_EXTRACTED_SIZE - self.extracted_size {
+            return Err(InstallationError::ExtractedSizeLimitError(MAX_EXTRACTED_SIZE));
+        }
+        Ok(())
+    }
+
+    // Links are never created, mods don't need them
+    // The ones pointing outside of the staging folder make the whole archive suspicious, so it is refused
+    fn check_symlink(&mut self, entry_name: &str, link_target: &str) -> Result<(), Box<dyn Error>> {
+        let entry_path = relative_entry_path(entry_name)?;
+        self.count_entry()?;
+        let escaping_error = || InstallationError::EscapingSymlinkEntryError {
+            entry: entry_name.to_string(),
+            target: link_target.to_string(),
+        };
+
+        // Resolve the target starting from the folder containing the link, without ever going above the staging folder
+        let normalized_target = link_target.replace('\\', "/");
+        let mut resolved_components = entry_path.parent().map(|parent| parent.components().count()).unwrap_or(0);
+        for component in Path::new(&normalized_target).components() {
+            match component {
+                Component::Normal(name) if has_drive_letter(&name.to_string_lossy()) => return Err(escaping_error().into()),
+                Component::Normal(_) => resolved_components += 1,
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    resolved_components = resolved_components.checked_sub(1).ok_or_else(escaping_error)?;
+                }
+                Component::RootDir | Component::Prefix(_) => return Err(escaping_error().into()),
+            }
+        }
+
+        eprintln!("Warning: Skipping \"{}\", symbolic links are not extracted", entry_name);
+        Ok(())
+    }
 }
 
-pub fn install_world_model(dtt_dat_folder_path: PathBuf, game_path: &PathBuf) -> Result<Mod, Box<dyn std::error::Error>> {
-    let answer = ask_mod_name()?;
+// Relative path of an archive entry, refused if it would end up outside of the staging folder
+fn relative_entry_path(entry_name: &str) -> Result<PathBuf, InstallationError> {
+    // Archives made on Windows use backslashes as separators
+    let normalized_name = entry_name.replace('\\', "/");
 
-    let bg_mods_folder = game_path.join("/data/bg");
+    let mut relative_path = PathBuf::new();
+    for component in Path::new(&normalized_name).components() {
+        match component {
+            Component::Normal(name) if relative_path.as_os_str().is_empty() && has_drive_letter(&name.to_string_lossy()) => {
+                return Err(InstallationError::AbsolutePathEntryError(entry_name.to_string()));
+            }
+            Component::Normal(name) => relative_path.push(name),
+            Component::CurDir => {}
+            Component::ParentDir => return Err(InstallationError::ParentFolderEntryError(entry_name.to_string())),
+            Component::RootDir | Component::Prefix(_) => {
+                return Err(InstallationError::AbsolutePathEntryError(entry_name.to_string()));
+            }
+        }
+    }
 
-    let mut mod_files: Vec<PathBuf> = vec![];
-    for entry in read_dir(dtt_dat_folder_path)? {
-        let current_entry = entry?;
-        let entry_path = current_entry.path();
+    Ok(relative_path)
+}
 
-        copy(&entry_path, &bg_mods_folder)?;
+// Windows absolute paths ("C:") are just a normal component for Linux
+fn has_drive_letter(component: &str) -> bool {
+    let mut characters = component.chars();
+    matches!((characters.next(), characters.next()), (Some(letter), Some(':')) if letter.is_ascii_alphabetic())
+}
+
+// Makes sure no link inside the staging folder points outside of it
+fn check_staged_symlinks(staging_folder: &Path) -> Result<(), Box<dyn Error>> {
+    let canonical_staging_folder = staging_folder.canonicalize()?;
 
-        mod_files.push(entry_path);
+    for entry in WalkDir::new(staging_folder) {
+        let current_entry = entry?;
+        if !current_entry.path_is_symlink() {
+            continue;
+        }
+
+        let link_target = read_link(current_entry.path())?;
+        let stays_inside = current_entry.path()
+            .canonicalize()
+            .is_ok_and(|resolved_path| resolved_path.starts_with(&canonical_staging_folder));
+        if !stays_inside {
+            return Err(InstallationError::EscapingSymlinkEntryError {
+                entry: current_entry.path().display().to_string(),
+                target: link_target.display().to_string(),
+            }.into());
+        }
     }
 
-    Ok(Mod::new(
-        answer,
-        mod_files,
-        true,
-        ModType::WorldModels,
-    ))
+    Ok(())
 }
 
-pub fn install_cutscene_replacements(usm_folder_path: PathBuf, game_path: &PathBuf) -> Result<Mod, Box<dyn std::error::Error>> {
-    let answer = ask_mod_name()?;
+pub fn get_file_extension(path: &Path) -> Result<&str, String> {
+    let Some(extension) = path.extension() else {
+        return Err(format!("{:?} is an extensionless file", path));
+    };
+    let Some(extension_str) = extension.to_str() else {
+       	return Err(format!("{:?} contains invalid UTF-8 in its extension", path));
+    };
     
-    let cutscene_mods_folder = game_path.join("/data/movie");
+    Ok(extension_str)
+}
 
-    let mut mod_files: Vec<PathBuf> = vec![];
-    for entry in read_dir(usm_folder_path)? {
-        let current_entry = entry?;
-        let entry_path = current_entry.path();
 
-        copy(&entry_path, &cutscene_mods_folder)?;
 
-        mod_files.push(entry_path);
-    }
+/* ------------------------ */
+/*   INSTALLATION METHODS   */
+/* ------------------------ */
+
+// Every method installs in the base folder the files laid out as the game folder expects them
+// ATA gives them the folder of the mod in the mod store, the game folder only gets copies of the files from there
+
+pub fn install_texture(textures: &DetectedComponent, base_folder: &Path) -> Result<ModComponent, Box<dyn Error>> {
+    let texture_mods_folder = base_folder.join("SK_Res/inject/textures");
 
-    Ok(Mod::new(
-        answer,
-        mod_files,
-        true,
-        ModType::CutsceneReplacements,
-    ))
+    let mod_files = copy_mod_files(textures, base_folder, &texture_mods_folder)?;
+
+    Ok(ModComponent::new(ModType::Textures, mod_files))
+}
+
+// Player, weapon, world... models all go in the folder inside NieRAutomata/data named after their prefix
+pub fn install_model(models: &DetectedComponent, base_folder: &Path) -> Result<ModComponent, Box<dyn std::error::Error>>  {
+    let Some(data_folder) = data_folder_of(models.mod_type) else {
+        return Err(format!("{:?} are not installed in the data folder", models.mod_type).into());
+    };
+    let model_mods_folder = base_folder.join("data").join(data_folder);
+
+    let mod_files = copy_mod_files(models, base_folder, &model_mods_folder)?;
+
+    Ok(ModComponent::new(models.mod_type, mod_files))
 }
 
-pub fn install_reshade_preset(preset_folder_path: PathBuf, game_path: &PathBuf) -> Result<Mod, Box<dyn std::error::Error>> {
-	Ok(Mod::new(String::from("Texture"), vec![], true, ModType::Textures))
+pub fn install_cutscene_replacements(cutscenes: &DetectedComponent, base_folder: &Path) -> Result<ModComponent, Box<dyn std::error::Error>> {
+    let cutscene_mods_folder = base_folder.join("data/movie");
+
+    let mod_files = copy_mod_files(cutscenes, base_folder, &cutscene_mods_folder)?;
+
+    Ok(ModComponent::new(ModType::CutsceneReplacements, mod_files))
+}
+
+pub fn install_reshade_preset(_preset: &DetectedComponent, _base_folder: &Path) -> Result<ModComponent, Box<dyn std::error::Error>> {
+	Ok(ModComponent::new(ModType::ReshadePreset, vec![]))
 }
 
 
@@ -257,12 +588,120 @@ pub fn install_reshade_preset(preset_folder_path: PathBuf, game_path: &PathBuf)
 /*   INSTALLATION FUNCTIONS   */
 /* -------------------------- */
 
-fn ask_mod_name() -> Result<String, std::io::Error> {
+// Copies the files of a component (the ones detection found, or the user chose the type of)
+// Files keep their path inside the game's layout, so nested folders (used a lot by texture packs) aren't flattened
+// Returns where each file ended up relative to the base folder, with what it contained at that moment
+fn copy_mod_files(component: &DetectedComponent, base_folder: &Path, target_folder: &Path) -> Result<Vec<InstalledFile>, Box<dyn Error>> {
+    let mut copied_files: Vec<PathBuf> = vec![];
+
+    for (file_path, relative_path) in component_file_paths(component) {
+        let target_path = target_folder.join(relative_path);
+        // Two copies of the same file (Mod/data/pl/pl0000.dtt and Mod/Optional/data/pl/pl0000.dtt), only one can be installed
+        if copied_files.contains(&target_path) {
+            return Err(format!("More than one file of the mod goes in {:?}", target_path.strip_prefix(base_folder).unwrap_or(&target_path)).into());
+        }
+
+        if let Some(target_parent) = target_path.parent() {
+            create_dir_all(target_parent)?;
+        }
+        copy(&file_path, &target_path)?;
+
+        copied_files.push(target_path);
+    }
+
+    copied_files
+        .iter()
+        .map(|copied_file| InstalledFile::from_installed_path(base_folder, copied_file))
+        .collect()
+}
+
+// Where each file of the component is going to be installed, relative to the game folder
+// Mirrors copy_mod_files, so it can be used to look at the game folder before anything is copied
+pub fn component_destinations(component: &DetectedComponent) -> Vec<PathBuf> {
+    let Some(game_folder) = game_folder_of(component.mod_type) else {
+        return vec![];
+    };
+
+    component_file_paths(component)
+        .into_iter()
+        .map(|(_, relative_path)| game_folder.join(relative_path))
+        .collect()
+}
+
+pub fn ask_mod_name() -> Result<String, std::io::Error> {
 	println!("Insert name of the mod that you are installing (choose anything you want, will be used as identifier)");
 	print!("Name: ");
 	stdout().flush()?;
 
 	let mut answer = String::new();
 	stdin().read_line(&mut answer)?;
-	Ok(answer)
+	Ok(answer.trim().to_string())
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symlink_check(entry_name: &str, link_target: &str) -> Result<(), Box<dyn Error>> {
+        ExtractionGuard::new(Path::new("/tmp/ata-staging")).check_symlink(entry_name, link_target)
+    }
+
+    fn is_escaping_symlink(result: Result<(), Box<dyn Error>>) -> bool {
+        result.is_err_and(|er| matches!(er.downcast_ref::<InstallationError>(), Some(InstallationError::EscapingSymlinkEntryError { .. })))
+    }
+
+    #[test]
+    fn entry_paths_stay_relative() {
+        assert_eq!(relative_entry_path("Mod/data/pl/pl0000.dtt").unwrap(), PathBuf::from("Mod/data/pl/pl0000.dtt"));
+        assert_eq!(relative_entry_path("./Mod/textures/a.dds").unwrap(), PathBuf::from("Mod/textures/a.dds"));
+    }
+
+    #[test]
+    fn backslashes_are_separators() {
+        assert_eq!(relative_entry_path("Mod\\data\\pl\\pl0000.dtt").unwrap(), PathBuf::from("Mod/data/pl/pl0000.dtt"));
+    }
+
+    #[test]
+    fn parent_folder_entries_are_refused() {
+        assert!(matches!(relative_entry_path("../pl0000.dtt"), Err(InstallationError::ParentFolderEntryError(_))));
+        assert!(matches!(relative_entry_path("Mod/../../pl0000.dtt"), Err(InstallationError::ParentFolderEntryError(_))));
+        assert!(matches!(relative_entry_path("Mod\\..\\..\\pl0000.dtt"), Err(InstallationError::ParentFolderEntryError(_))));
+    }
+
+    #[test]
+    fn absolute_entries_are_refused() {
+        assert!(matches!(relative_entry_path("/etc/passwd"), Err(InstallationError::AbsolutePathEntryError(_))));
+        assert!(matches!(relative_entry_path("C:\\Windows\\win.ini"), Err(InstallationError::AbsolutePathEntryError(_))));
+        assert!(matches!(relative_entry_path("c:/Windows/win.ini"), Err(InstallationError::AbsolutePathEntryError(_))));
+    }
+
+    #[test]
+    fn folders_and_symlinks_count_as_files() {
+        let mut extraction_guard = ExtractionGuard::new(Path::new("/tmp/ata-staging"));
+        extraction_guard.extracted_files = MAX_EXTRACTED_FILES - 1;
+        assert!(extraction_guard.check_symlink("Mod/link.dds", "a.dds").is_ok());
+
+        let folder_error = extraction_guard.create_folder("Mod/folder").unwrap_err();
+        assert!(matches!(folder_error.downcast_ref::<InstallationError>(), Some(InstallationError::ExtractedFilesLimitError(_))));
+        let symlink_error = extraction_guard.check_symlink("Mod/other_link.dds", "a.dds").unwrap_err();
+        assert!(matches!(symlink_error.downcast_ref::<InstallationError>(), Some(InstallationError::ExtractedFilesLimitError(_))));
+    }
+
+    #[test]
+    fn contained_symlinks_are_skipped() {
+        assert!(symlink_check("Mod/link.dds", "a.dds").is_ok());
+        assert!(symlink_check("Mod/textures/link.dds", "../a.dds").is_ok());
+        assert!(symlink_check("Mod\\textures\\link.dds", "..\\..\\a.dds").is_ok());
+    }
+
+    #[test]
+    fn escaping_symlinks_are_refused() {
+        assert!(is_escaping_symlink(symlink_check("link.dds", "../a.dds")));
+        assert!(is_escaping_symlink(symlink_check("Mod/link.dds", "../../a.dds")));
+        assert!(is_escaping_symlink(symlink_check("Mod/link.dds", "..\\..\\a.dds")));
+        assert!(is_escaping_symlink(symlink_check("Mod/link.dds", "/etc/passwd")));
+        assert!(is_escaping_symlink(symlink_check("Mod/link.dds", "C:\\Windows\\win.ini")));
+    }
 }
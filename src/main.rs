@@ -1,11 +1,15 @@
-use std::error::Error;
+use std::process::ExitCode;
 
 use clap::Parser;
 
-use clearscreen::clear;
+mod ata_folders;
+use ata_folders::init_ata_folders;
+
+mod ata_log;
+use ata_log::log_event;
 
 mod data_saving;
-use data_saving::{Config, Mod};
+use data_saving::{data_file_path, is_broken_data_file_error, Config, ConfigError};
 
 mod starting_checks;
 use starting_checks::{
@@ -14,17 +18,66 @@ use starting_checks::{
 
 mod user_interactions;
 use user_interactions::{
-    ask_for_correct_gamepath, missing_files_warning, run_auto_install_script, ask_user_action, ask_for_mod_folder
+    ask_for_correct_gamepath, missing_files_warning, run_auto_install_script, ask_user_action, ask_for_mod_folder,
+    ask_for_installed_mod, ask_order_place,
 };
 
 mod features;
-use features::{install_mod, uninstall_mod, list_mods};
+use features::{install_mod, uninstall_mod, enable_mod, disable_mod, list_mods, filter_and_sort_mods, installed_mod, mod_info, show_mod_info, load_order, show_load_order, move_mod, InstallChoices, ModFilter, ModSorting, ModifiedFiles, ModManagingError, OrderPlace};
 
 mod installation_utilities_and_methods;
 
+mod mod_detection;
+
+mod vanilla_backups;
+
+mod mod_store;
+
+mod mod_conflicts;
+
+mod deployment;
+use deployment::{repair_game_folder, show_repair_report};
 
+mod config_recovery;
+use config_recovery::recover_config;
+
+mod output;
+use output::{print_error, set_output_format, OutputFormat};
+
+mod cli;
+use cli::{json_output_asked, run_command, Args};
+
+
+
+fn main() -> ExitCode {
+    let args = match Args::try_parse() {
+        Ok(args) => args,
+        // Scripts asking for JSON get usage errors as JSON too, --help and --version are printed as usual
+        Err(er) if er.use_stderr() && json_output_asked() => {
+            set_output_format(OutputFormat::Json);
+            print_error(&er);
+            return ExitCode::from(2);
+        }
+        Err(er) => er.exit(),
+    };
+
+    // FIND WHERE ATA KEEPS ITS FILES
+    if let Err(er) = init_ata_folders() {
+        set_output_format(args.output);
+        print_error(&er);
+        return ExitCode::FAILURE;
+    }
+
+    // RUN THE GIVEN COMMAND WITHOUT ASKING ANYTHING
+    if let Some(command) = args.command {
+        set_output_format(args.output);
+
+        return run_command(command).unwrap_or_else(|er| {
+            print_error(er.as_ref());
+            ExitCode::FAILURE
+        });
+    }
 
-fn main() {
     println!("\nWELCOME TO ACCORD'S TIMELINE ALTERER\n(AUTOMATA'S MOD MANAGER FOR LINUX)\n\n");
 
 
@@ -34,16 +87,43 @@ fn main() {
     /* ----------------------- */
 
     // LOAD DATA IF PRESENT
-    println!("Loading data file (~/.config/ATA/data.json)");
+    println!("Loading data file ({:?})", data_file_path());
     
-    let mut current_config = Config::load_config()
-    .unwrap_or_else(|err| {
-        eprintln!("There was a problem accessing the data file (~/.config/ATA/data.json). {}\nConsider checking if the file is there and if it isn't corrupted.
+    let mut current_config = match Config::load_config() {
+        Ok(config) => config,
+        // Nothing is wrong with the file, this ATA is just too old to read it
+        Err(err) if err.downcast_ref::<ConfigError>().is_some_and(|err| matches!(err, ConfigError::NewerSchemaError { .. })) => {
+            eprintln!("{}\nThe data file was left untouched.", err);
+            return ExitCode::FAILURE;
+        }
+        // The file is there but what's in it can't be used, the user can get a working one back
+        Err(err) if is_broken_data_file_error(err.as_ref()) => {
+            eprintln!("The data file can't be read, it was probably corrupted. {}\n", err);
+
+            match recover_config() {
+                Ok(Some(config)) => {
+                    log_event("Recovered a data file that couldn't be read");
+                    config
+                }
+                Ok(None) => {
+                    println!("ATA will now close...");
+                    return ExitCode::FAILURE;
+                }
+                Err(er) => {
+                    eprintln!("There was a problem recovering the data file. {}
+                            ATA will now close...", er);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Err(err) => {
+            eprintln!("There was a problem accessing the data file. {}\nConsider checking if the file is there and if it isn't corrupted.
                 ATA will now close...", err);
-        std::process::exit(1);
-    });
+            std::process::exit(1);
+        }
+    };
     
-    println!("Config file (~/.config/ATA/data.json) loaded!\n");
+    println!("Config file loaded!\n");
     
 
 
@@ -80,13 +160,20 @@ fn main() {
         
     println!("Game installation found at {:?}\n", current_config.game_path);
 
+    // The game path could have just been changed, it isn't asked again next time
+    current_config.save_config().unwrap_or_else(|er| {
+        eprintln!("There was an error saving the game path to the data file. {}
+                ATA will now close...", er);
+        std::process::exit(1);
+    });
+
     
     
     // CHECKING IF THE REQUIRED MODDING FILES ARE INSTALLED
     println!("Checking if the required modding files are installed");
     
     let missing_required_modding_files = check_for_required_modding_files(&current_config.game_path);
-    if missing_required_modding_files.len() > 0 {
+    if !missing_required_modding_files.is_empty() {
         let user_answer = missing_files_warning(missing_required_modding_files)
             .unwrap_or_else(|er| {
                 eprintln!("There has been a problem using the console to warn you about the missing required modding files. {}
@@ -140,24 +227,155 @@ fn main() {
                 std::process::exit(1);
             });
 
-        	let installed_mod = install_mod(&current_config.game_path, answered_path).unwrap_or_else(|er| {
-             	eprintln!("There was a problem installing the mod. {}", er);
-               	std::process::exit(1);
-            });
-            println!("MOD INSTALLED");
-                    
-            save_mod_data(installed_mod).unwrap_or_else(|er| {
-                println!("There was an error saving the data of the installed mod to the data file (~/.config/ATA/data.json). {}", er);
+            match install_mod(&mut current_config, &answered_path, InstallChoices::Ask) {
+                Ok(_) => {}
+                Err(er) if er.downcast_ref::<ModManagingError>().is_some_and(|er| matches!(er, ModManagingError::ConflictingFileError(_))) => {
+                    println!("{}\n", er);
+                    continue;
+                }
+                // A mod that can't be installed doesn't close ATA, another one can be tried right away
+                Err(er) => {
+                    eprintln!("There was a problem installing the mod. {}\n", er);
+                    continue;
+                }
+            }
+            current_config.save_config().unwrap_or_else(|er| {
+                println!("There was an error saving the data of the installed mod to the data file. {}", er);
                 std::process::exit(1);
             });
+
+            if let Some(installed_mod) = current_config.mods.last() {
+                println!("MOD \"{}\" INSTALLED (ID: {})", installed_mod.name(), installed_mod.id());
+                log_event(&format!("Installed \"{}\" (ID: {}) from {:?}", installed_mod.name(), installed_mod.id(), answered_path));
+            }
         }
         // UNINSTALL A MOD
         else if action_id == "2" {
-        	uninstall_mod(&current_config.game_path);
+            let answered_mod = ask_for_installed_mod("uninstall").unwrap_or_else(|er| {
+                eprintln!("There was a problem using the console for asking for the mod to uninstall. {}
+                        ATA will now close...", er);
+                std::process::exit(1);
+            });
+
+        	match uninstall_mod(&mut current_config, &answered_mod, ModifiedFiles::Ask) {
+                Ok(uninstalled_mod) => {
+                    println!("MOD \"{}\" UNINSTALLED", uninstalled_mod.name());
+                    log_event(&format!("Uninstalled \"{}\" (ID: {})", uninstalled_mod.name(), uninstalled_mod.id()));
+
+                    current_config.save_config().unwrap_or_else(|er| {
+                        println!("There was an error removing the uninstalled mod from the data file. {}", er);
+                        std::process::exit(1);
+                    });
+                }
+                Err(er) => eprintln!("There was a problem uninstalling the mod. {}", er),
+            }
         } 
         // PRINT THE LIST OF INSTALLED MODS
         else if action_id == "3" {
-            list_mods(&current_config.mods);
+            list_mods(&filter_and_sort_mods(&current_config.mods, &ModFilter::default(), ModSorting::Id));
+        }
+        // ENABLE A MOD
+        else if action_id == "4" {
+            let answered_mod = ask_for_installed_mod("enable").unwrap_or_else(|er| {
+                eprintln!("There was a problem using the console for asking for the mod to enable. {}
+                        ATA will now close...", er);
+                std::process::exit(1);
+            });
+
+            match enable_mod(&mut current_config, &answered_mod) {
+                Ok(enabled_mod) => {
+                    println!("MOD \"{}\" ENABLED", enabled_mod.name());
+                    log_event(&format!("Enabled \"{}\" (ID: {})", enabled_mod.name(), enabled_mod.id()));
+
+                    current_config.save_config().unwrap_or_else(|er| {
+                        println!("There was an error saving the enabled mod to the data file. {}", er);
+                        std::process::exit(1);
+                    });
+                }
+                Err(er) => eprintln!("There was a problem enabling the mod. {}", er),
+            }
+        }
+        // DISABLE A MOD
+        else if action_id == "5" {
+            let answered_mod = ask_for_installed_mod("disable").unwrap_or_else(|er| {
+                eprintln!("There was a problem using the console for asking for the mod to disable. {}
+                        ATA will now close...", er);
+                std::process::exit(1);
+            });
+
+            match disable_mod(&mut current_config, &answered_mod) {
+                Ok(disabled_mod) => {
+                    println!("MOD \"{}\" DISABLED", disabled_mod.name());
+                    log_event(&format!("Disabled \"{}\" (ID: {})", disabled_mod.name(), disabled_mod.id()));
+
+                    current_config.save_config().unwrap_or_else(|er| {
+                        println!("There was an error saving the disabled mod to the data file. {}", er);
+                        std::process::exit(1);
+                    });
+                }
+                Err(er) => eprintln!("There was a problem disabling the mod. {}", er),
+            }
+        }
+        // SHOW THE DETAILS OF A MOD
+        else if action_id == "6" {
+            let answered_mod = ask_for_installed_mod("show").unwrap_or_else(|er| {
+                eprintln!("There was a problem using the console for asking for the mod to show. {}
+                        ATA will now close...", er);
+                std::process::exit(1);
+            });
+
+            match installed_mod(&current_config, &answered_mod) {
+                Ok(shown_mod) => show_mod_info(&mod_info(&current_config, shown_mod)),
+                Err(er) => eprintln!("{}", er),
+            }
+        }
+        // CHANGE THE LOAD ORDER
+        else if action_id == "7" {
+            show_load_order(&load_order(&current_config));
+            println!();
+
+            let answered_mod = ask_for_installed_mod("move").unwrap_or_else(|er| {
+                eprintln!("There was a problem using the console for asking for the mod to move. {}
+                        ATA will now close...", er);
+                std::process::exit(1);
+            });
+            let answered_place = ask_order_place().unwrap_or_else(|er| {
+                eprintln!("There was a problem using the console for asking where to move the mod. {}
+                        ATA will now close...", er);
+                std::process::exit(1);
+            });
+
+            let place = match current_config.mods.last() {
+                Some(last_mod) if answered_place.is_empty() => OrderPlace::After(last_mod.id().to_string()),
+                _ => OrderPlace::Before(answered_place),
+            };
+            match move_mod(&mut current_config, &answered_mod, &place) {
+                Ok(moved_mod) => {
+                    println!("MOD \"{}\" MOVED", moved_mod.name());
+                    log_event(&format!("Moved \"{}\" (ID: {}) in the load order", moved_mod.name(), moved_mod.id()));
+
+                    current_config.save_config().unwrap_or_else(|er| {
+                        println!("There was an error saving the load order to the data file. {}", er);
+                        std::process::exit(1);
+                    });
+                }
+                Err(er) => eprintln!("There was a problem moving the mod. {}", er),
+            }
+        }
+        // REPAIR THE GAME FOLDER
+        else if action_id == "8" {
+            match repair_game_folder(&mut current_config) {
+                Ok(report) => {
+                    show_repair_report(&report);
+                    log_event(&format!("Repaired the game folder ({} files put back)", report.redeployed_files.len()));
+
+                    current_config.save_config().unwrap_or_else(|er| {
+                        println!("There was an error saving the repaired mods to the data file. {}", er);
+                        std::process::exit(1);
+                    });
+                }
+                Err(er) => eprintln!("There was a problem repairing the game folder. {}", er),
+            }
         }
         // EXIT THE PROGRAM
         else if action_id == "0" {
@@ -165,43 +383,9 @@ fn main() {
             std::process::exit(1);
         }
         else {
-            println!("\"{}\" is not a valid action id (input either 1, 2, 3 or 0)", action_id);
+            println!("\"{}\" is not a valid action id (input either 1, 2, 3, 4, 5, 6, 7, 8 or 0)", action_id);
         }
     }
-}
-
-
-
-
-
-
-/* ----- */
-/*   SHOULD MOVE TO USER INTERACTION FILW WITH MISSING FILES WQARING   */
-/* ----- */
-
-
-
-fn save_mod_data(mod_data: Mod) -> Result<(), Box<dyn Error>> {
-	Ok(())
-}
-
-
-
-
-
-
-/* ---------------------------- */
-/*   FLAGS FOR QUICK FEATURES   */
-/* ---------------------------- */
 
-#[derive(Parser)]
-#[command(
-    name = "NAMHL",
-    version = "0.01",
-    about = "The Nier Automata Mod Helper for Linux"
-)]
-struct Args {
-    folder_path: String,
-    mod_name: String,
-    // Will add arguments here
+    ExitCode::SUCCESS
 }
@@ -0,0 +1,169 @@
+use std::collections::BTreeSet;
+
+use std::path::{Path, PathBuf};
+
+use clap::ValueEnum;
+
+use crate::data_saving::{Config, InstalledFile, Mod, ModComponent, ModType};
+
+use crate::mod_detection::{game_folder_of, mod_type_in_game_folder};
+
+
+
+// What to do with a file the mod being installed has in common with installed mods
+#[derive(ValueEnum, Clone, Copy, PartialEq)]
+pub enum ConflictChoice {
+    /// Don't install the mod
+    Skip,
+    /// Put the file of the new mod in the game folder
+    Overwrite,
+    /// Leave the file of the installed mod in the game folder
+    Keep,
+}
+
+// A file of the mod being installed that installed mods have too, relative to the game folder
+pub struct FileConflict {
+    pub path: PathBuf,
+    pub other_mods: Vec<(u32, String)>,     // ID and name of the installed mods that have it
+}
+
+// What two files have to share to take the same place in the game
+// SpecialK picks textures by their file name (the hash of the texture they replace) wherever they are in its textures folder,
+// so SK_Res/inject/textures/a/ABCD.dds and SK_Res/inject/textures/b/abcd.dds are the same texture, every other file is found by its path
+pub fn conflict_key(game_relative_path: &Path) -> PathBuf {
+    if mod_type_in_game_folder(game_relative_path) == Some(ModType::Textures)
+        && let Some(textures_folder) = game_folder_of(ModType::Textures)
+        && let Some(file_name) = game_relative_path.file_name()
+    {
+        return textures_folder.join(file_name.to_string_lossy().to_lowercase());
+    }
+
+    game_relative_path.to_path_buf()
+}
+
+// File of the mod that takes the place in the game the conflict key stands for
+pub fn file_with_key<'a>(saved_mod: &'a Mod, key: &Path) -> Option<&'a InstalledFile> {
+    saved_mod.components().iter().flat_map(ModComponent::files).find(|installed_file| conflict_key(&installed_file.path) == key)
+}
+
+// Files among the given ones (relative to the game folder) that installed mods have too, enabled or not
+pub fn find_conflicts(config: &Config, new_files: &[PathBuf]) -> Vec<FileConflict> {
+    new_files
+        .iter()
+        .filter_map(|new_file| {
+            let key = conflict_key(new_file);
+            let other_mods: Vec<(u32, String)> = config.mods
+                .iter()
+                .filter(|installed_mod| file_with_key(installed_mod, &key).is_some())
+                .map(|installed_mod| (installed_mod.id(), installed_mod.name().to_string()))
+                .collect();
+
+            (!other_mods.is_empty()).then(|| FileConflict { path: new_file.clone(), other_mods })
+        })
+        .collect()
+}
+
+// Mod whose copy of the file should be in the game folder, among the ones that have it (or a file with the same conflict key)
+// The one the user chose, if it still has the file, otherwise the last one in the load order (the order of the mods in the config)
+pub fn winning_mod(config: &Config, game_relative_path: &Path, only_enabled: bool) -> Option<u32> {
+    let key = conflict_key(game_relative_path);
+    let mods_with_file: Vec<&Mod> = config.mods
+        .iter()
+        .filter(|saved_mod| file_with_key(saved_mod, &key).is_some() && (saved_mod.enabled() || !only_enabled))
+        .collect();
+    let chosen_winner = config.conflict_winners.get(&key);
+
+    mods_with_file
+        .iter()
+        .find(|saved_mod| Some(&saved_mod.id()) == chosen_winner)
+        .or(mods_with_file.last())
+        .map(|saved_mod| saved_mod.id())
+}
+
+// Conflict keys of the files more than one installed mod has, sorted so that redeploying them always goes the same way
+pub fn contested_files(config: &Config) -> BTreeSet<PathBuf> {
+    let mut seen_keys: BTreeSet<PathBuf> = BTreeSet::new();
+    let mut contested_keys: BTreeSet<PathBuf> = BTreeSet::new();
+    for saved_mod in &config.mods {
+        // A mod having the same texture twice doesn't make it contested
+        let mod_keys: BTreeSet<PathBuf> = saved_mod
+            .components()
+            .iter()
+            .flat_map(ModComponent::files)
+            .map(|installed_file| conflict_key(&installed_file.path))
+            .collect();
+
+        for key in mod_keys {
+            if !seen_keys.insert(key.clone()) {
+                contested_keys.insert(key);
+            }
+        }
+    }
+
+    contested_keys
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::ata_folders::init_test_ata_folders;
+
+    use crate::deployment::redeploy_file;
+
+    // Config whose mods all have the same file, in the given load order, without anything in the mod store
+    fn config_with_mods(mod_ids: &[u32], game_relative_path: &str) -> Config {
+        let ata_folders = init_test_ata_folders();
+        let mut config = Config::default();
+        config.game_path = ata_folders.user_data.join(format!("conflicts-game-{}", mod_ids[0]));
+
+        for &mod_id in mod_ids {
+            let installed_file = InstalledFile { path: PathBuf::from(game_relative_path), size: 0, hash: format!("hash of {}", mod_id) };
+            let components = vec![ModComponent::new(ModType::PlayerModels, vec![installed_file])];
+            config.add_mod_with_id(Mod::new(format!("Mod {}", mod_id), None, components, vec![], true), mod_id);
+        }
+        config
+    }
+
+    #[test]
+    fn textures_conflict_by_name_only() {
+        assert_eq!(conflict_key(Path::new("SK_Res/inject/textures/a/ABCD.dds")), conflict_key(Path::new("SK_Res/inject/textures/b/abcd.dds")));
+        assert_eq!(conflict_key(Path::new("SK_Res/inject/textures/ABCD.dds")), PathBuf::from("SK_Res/inject/textures/abcd.dds"));
+        assert_ne!(conflict_key(Path::new("SK_Res/inject/textures/ABCD.dds")), conflict_key(Path::new("SK_Res/inject/textures/ABCE.dds")));
+    }
+
+    #[test]
+    fn other_files_conflict_by_path() {
+        assert_eq!(conflict_key(Path::new("data/pl/pl0000.dtt")), PathBuf::from("data/pl/pl0000.dtt"));
+        assert_ne!(conflict_key(Path::new("data/pl/pl0000.dtt")), conflict_key(Path::new("data/pl/PL0000.dtt")));
+    }
+
+    #[test]
+    fn chosen_winner_beats_load_order() {
+        let mut config = config_with_mods(&[9100, 9101, 9102], "data/pl/pl9100.dtt");
+        assert_eq!(winning_mod(&config, Path::new("data/pl/pl9100.dtt"), true), Some(9102));
+
+        config.conflict_winners.insert(PathBuf::from("data/pl/pl9100.dtt"), 9100);
+        assert_eq!(winning_mod(&config, Path::new("data/pl/pl9100.dtt"), true), Some(9100));
+    }
+
+    #[test]
+    fn winner_without_the_file_is_dropped() {
+        let mut config = config_with_mods(&[9110, 9111, 9112], "data/pl/pl9110.dtt");
+        config.conflict_winners.insert(PathBuf::from("data/pl/pl9110.dtt"), 9110);
+
+        // The chosen mod doesn't have the file anymore, the load order decides again
+        config.mods.retain(|saved_mod| saved_mod.id() != 9110);
+        assert_eq!(winning_mod(&config, Path::new("data/pl/pl9110.dtt"), true), Some(9112));
+        redeploy_file(&mut config, Path::new("data/pl/pl9110.dtt")).unwrap();
+        assert!(config.conflict_winners.is_empty());
+
+        // Only one mod has it, there's nothing to choose
+        config.conflict_winners.insert(PathBuf::from("data/pl/pl9110.dtt"), 9111);
+        config.mods.retain(|saved_mod| saved_mod.id() != 9112);
+        redeploy_file(&mut config, Path::new("data/pl/pl9110.dtt")).unwrap();
+        assert!(config.conflict_winners.is_empty());
+    }
+}
@@ -0,0 +1,394 @@
+use std::error::Error;
+
+use std::path::{Path, PathBuf};
+
+use walkdir::WalkDir;
+
+use crate::data_saving::ModType;
+
+use crate::installation_utilities_and_methods::get_file_extension;
+
+use crate::output::print_notice;
+
+
+
+/* ----------------------- */
+/*   FILE CLASSIFICATION   */
+/* ----------------------- */
+
+// Folders inside NieRAutomata/data, every file in them is named with the folder's name followed by a numeric ID
+// (data/pl/pl0000.dtt, data/wp/wp0010.dat, data/bg/bg0110.dat...)
+const DATA_FOLDERS: [(&str, ModType); 8] = [
+    ("pl", ModType::PlayerModels),
+    ("wp", ModType::WeaponModels),
+    ("bg", ModType::WorldModels),
+    ("em", ModType::EnemyModels),
+    ("um", ModType::ObjectModels),
+    ("et", ModType::EffectModels),
+    ("ui", ModType::InterfaceFiles),
+    ("it", ModType::ItemModels),
+];
+
+// Folder inside NieRAutomata/data where the files of the given type go
+pub fn data_folder_of(mod_type: ModType) -> Option<&'static str> {
+    DATA_FOLDERS
+        .iter()
+        .find(|(_, folder_type)| *folder_type == mod_type)
+        .map(|(folder, _)| *folder)
+}
+
+// Folder of the game, relative to the game path, where the files of the given type go
+pub fn game_folder_of(mod_type: ModType) -> Option<PathBuf> {
+    match mod_type {
+        ModType::Textures => Some(PathBuf::from("SK_Res/inject/textures")),
+        ModType::CutsceneReplacements => Some(PathBuf::from("data/movie")),
+        ModType::ReshadePreset => None,
+        data_type => data_folder_of(data_type).map(|data_folder| Path::new("data").join(data_folder)),
+    }
+}
+
+// Type of the mods that put files in the game folder the file is in (data/pl/pl0000.dtt -> PlayerModels)
+pub fn mod_type_in_game_folder(game_relative_path: &Path) -> Option<ModType> {
+    ModType::ALL
+        .into_iter()
+        .find(|mod_type| game_folder_of(*mod_type).is_some_and(|game_folder| game_relative_path.starts_with(game_folder)))
+}
+
+// Scores given to a file depending on how much it looks like the file of a mod
+const EXTENSION_ONLY_SCORE: f32 = 0.4;      // Right extension, unknown name (data/pl/custom.dat)
+const NAME_PATTERN_SCORE: f32 = 0.8;        // Right extension and name pattern (pl0000.dtt, anything.dds)
+const GAME_FOLDER_BONUS: f32 = 0.2;         // Inside the folder the game expects it in (data/pl/pl0000.dtt)
+
+// Type of mod the file most likely belongs to, with how sure the guess is (from 0 to 1)
+pub fn score_file(file_path: &Path) -> Option<(ModType, f32)> {
+    let extension = get_file_extension(file_path).ok()?.to_lowercase();
+    let parent_folder = file_path
+        .parent()
+        .and_then(|parent| parent.file_name())
+        .and_then(|name| name.to_str())
+        .map(|name| name.to_lowercase())
+        .unwrap_or_default();
+    let folder_bonus = |expected_folders: &[&str]| {
+        if expected_folders.contains(&parent_folder.as_str()) { GAME_FOLDER_BONUS } else { 0.0 }
+    };
+
+    match extension.as_str() {
+        "dds" => {
+            // Texture packs use nested folders, so any folder above the file counts
+            let in_textures_folder = file_path
+                .ancestors()
+                .skip(1)
+                .filter_map(|folder| folder.file_name())
+                .any(|name| name.eq_ignore_ascii_case("textures"));
+            let bonus = if in_textures_folder { GAME_FOLDER_BONUS } else { 0.0 };
+            Some((ModType::Textures, NAME_PATTERN_SCORE + bonus))
+        }
+        "usm" => Some((ModType::CutsceneReplacements, NAME_PATTERN_SCORE + folder_bonus(&["movie"]))),
+        "dtt" | "dat" => {
+            let Some(file_stem) = file_path.file_stem().and_then(|stem| stem.to_str()) else {
+                print_notice(&format!("\"{:?}\" contains invalid Unicode in its name and will therefore will be skipped", file_path));
+                return None;
+            };
+
+            // A file named like the data files, or a file of unknown name in one of the data folders
+            if let Some((data_folder, mod_type)) = classify_data_file(&file_stem.to_lowercase()) {
+                Some((mod_type, NAME_PATTERN_SCORE + folder_bonus(&[data_folder])))
+            } else {
+                DATA_FOLDERS
+                    .iter()
+                    .find(|(data_folder, _)| *data_folder == parent_folder)
+                    .map(|(_, mod_type)| (*mod_type, EXTENSION_ONLY_SCORE))
+            }
+        }
+        // RESHADE
+        _ => None,
+    }
+}
+
+// Data files are recognized by their prefix followed by a 4 digits hexadecimal ID (pl000d, wp0010)
+// and, at most, a suffix separated by an underscore (pl0000_2)
+// UI files are the exception, they are named after what they contain (ui_core_us)
+fn classify_data_file(file_stem: &str) -> Option<(&'static str, ModType)> {
+    DATA_FOLDERS
+        .iter()
+        .find(|(prefix, folder_type)| {
+            let Some(identifier) = file_stem.strip_prefix(prefix) else {
+                return false;
+            };
+            if *folder_type == ModType::InterfaceFiles && identifier.starts_with('_') {
+                return true;
+            }
+            has_numeric_id(identifier)
+        })
+        .copied()
+}
+
+fn has_numeric_id(identifier: &str) -> bool {
+    let (Some(id), Some(suffix)) = (identifier.get(..4), identifier.get(4..)) else {
+        return false;
+    };
+    id.chars().all(|character| character.is_ascii_hexdigit())
+        && (suffix.is_empty() || suffix.starts_with('_'))
+}
+
+
+
+/* -------------------- */
+/*   DETECTION REPORT   */
+/* -------------------- */
+
+// A type of mod found in the mod folder
+pub struct DetectedComponent {
+    pub mod_type: ModType,
+    pub confidence: f32,        // Average score of the files that made ATA think this is the type (from 0 to 1)
+    pub root: PathBuf,          // Deepest folder containing all the files, the game's layout is looked for inside it
+    pub files: Vec<PathBuf>,    // Files that made ATA think this is the type, they're the ones that get installed
+}
+
+// Everything ATA found in the mod folder, shown to the user before anything is copied
+pub struct DetectionReport {
+    pub components: Vec<DetectedComponent>,
+    pub unclassified_files: Vec<PathBuf>,
+}
+
+impl DetectionReport {
+    // Makes the files ATA didn't recognize part of the component of the given type, when the user says what they are
+    // (the root of the component is found again once the types are final)
+    pub fn classify_unclassified_files(&mut self, mod_type: ModType) {
+        let unclassified_files = std::mem::take(&mut self.unclassified_files);
+        if unclassified_files.is_empty() {
+            return;
+        }
+
+        match self.components.iter_mut().find(|component| component.mod_type == mod_type) {
+            Some(component) => {
+                component.files.extend(unclassified_files);
+                component.files.sort();
+            }
+            None => self.components.push(DetectedComponent {
+                mod_type,
+                confidence: 0.0,
+                root: PathBuf::new(),
+                files: unclassified_files,
+            }),
+        }
+    }
+}
+
+// Scores every file in the mod folder and groups them by type (bundles often ship textures and models together)
+pub fn check_mod_type(mod_folder_path: &Path) -> Result<DetectionReport, Box<dyn Error>> {
+    let mut components: Vec<DetectedComponent> = vec![];
+    let mut unclassified_files: Vec<PathBuf> = vec![];
+    
+    // Start looking at the contents of mod folder
+    for entry in WalkDir::new(mod_folder_path).sort_by_file_name() {
+        let current_entry = entry?;
+        let entry_path = current_entry.path();
+        
+        // Skip folders
+        if !current_entry.file_type().is_file() {
+           	continue
+        }         
+        let Some((mod_type, score)) = score_file(entry_path) else {
+            unclassified_files.push(entry_path.to_path_buf());
+            continue;
+        };
+        let Some(entry_folder) = entry_path.parent() else {
+            unclassified_files.push(entry_path.to_path_buf());
+            continue;
+        };
+
+        match components.iter_mut().find(|component| component.mod_type == mod_type) {
+            Some(component) => {
+                component.confidence += score;
+                component.files.push(entry_path.to_path_buf());
+            }
+            None => components.push(DetectedComponent {
+                mod_type,
+                confidence: score,
+                root: entry_folder.to_path_buf(),
+                files: vec![entry_path.to_path_buf()],
+            }),
+        }
+    }
+
+    // Until now confidence was the sum of the scores
+    for component in components.iter_mut() {
+        component.confidence /= component.files.len() as f32;
+        component.root = find_component_root(&component.files, mod_folder_path);
+    }
+    
+    Ok(DetectionReport { components, unclassified_files })
+}
+
+// Deepest folder containing all the files of the component, where ATA starts looking for the game's layout
+pub fn find_component_root(files: &[PathBuf], mod_folder_path: &Path) -> PathBuf {
+    files
+        .iter()
+        .filter_map(|file| file.parent())
+        .fold(None, |root: Option<PathBuf>, folder| match root {
+            Some(root) => Some(common_folder(&root, folder)),
+            None => Some(folder.to_path_buf()),
+        })
+        .unwrap_or_else(|| mod_folder_path.to_path_buf())
+}
+
+// Archives often wrap the files in extra folders (ModName/v1.2/data/pl/pl000d.dtt), or already mirror the game
+// (data/pl/..., SK_Res/inject/textures/...), sometimes only for some of the files (Mod/data/pl/... and Mod/Optional/...)
+// Files in a folder mirroring the game folder of the type lose everything up to it, the others keep their path relative
+// to the deepest folder containing all of them
+// Returns each file of the component with its path relative to the game folder of the type
+pub fn component_file_paths(component: &DetectedComponent) -> Vec<(PathBuf, PathBuf)> {
+    let mut unmirrored_files: Vec<PathBuf> = vec![];
+    let mut file_paths: Vec<(PathBuf, PathBuf)> = vec![];
+
+    for file in &component.files {
+        match mirrored_game_folder(file, component.mod_type, &component.root)
+            .and_then(|mirrored_folder| file.strip_prefix(mirrored_folder).ok().map(Path::to_path_buf))
+        {
+            Some(relative_path) => file_paths.push((file.clone(), relative_path)),
+            None => unmirrored_files.push(file.clone()),
+        }
+    }
+
+    let unmirrored_root = find_component_root(&unmirrored_files, &component.root);
+    for file in unmirrored_files {
+        if let Ok(relative_path) = file.strip_prefix(&unmirrored_root) {
+            let relative_path = relative_path.to_path_buf();
+            file_paths.push((file, relative_path));
+        }
+    }
+
+    file_paths
+}
+
+// Outermost folder containing the file, inside the search folder (included), that ends like the game folder of the type
+// (data/pl, inject/textures)
+fn mirrored_game_folder(file_path: &Path, mod_type: ModType, search_folder: &Path) -> Option<PathBuf> {
+    let game_folder = game_folder_of(mod_type)?;
+    let game_folder_end = last_two_components(&game_folder);
+
+    let mut folders: Vec<&Path> = file_path
+        .ancestors()
+        .skip(1)
+        .take_while(|folder| folder.starts_with(search_folder))
+        .collect();
+    folders.reverse();
+
+    folders
+        .into_iter()
+        .find(|folder| last_two_components(folder) == game_folder_end)
+        .map(Path::to_path_buf)
+}
+
+fn last_two_components(path: &Path) -> Vec<String> {
+    let mut components: Vec<String> = path
+        .components()
+        .rev()
+        .take(2)
+        .map(|component| component.as_os_str().to_string_lossy().to_lowercase())
+        .collect();
+    components.reverse();
+    components
+}
+
+fn common_folder(first_folder: &Path, second_folder: &Path) -> PathBuf {
+    first_folder
+        .components()
+        .zip(second_folder.components())
+        .take_while(|(first_component, second_component)| first_component == second_component)
+        .map(|(component, _)| component)
+        .collect()
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detected_component(mod_type: ModType, files: &[&str]) -> DetectedComponent {
+        let files: Vec<PathBuf> = files.iter().map(PathBuf::from).collect();
+        DetectedComponent { mod_type, confidence: 1.0, root: find_component_root(&files, Path::new("Mod")), files }
+    }
+
+    #[test]
+    fn data_files_are_classified_by_name() {
+        let cases = [
+            ("pl000d", Some(("pl", ModType::PlayerModels))),
+            ("pl0000", Some(("pl", ModType::PlayerModels))),
+            ("wp0010_2", Some(("wp", ModType::WeaponModels))),
+            ("bg0110", Some(("bg", ModType::WorldModels))),
+            ("ui_core_us", Some(("ui", ModType::InterfaceFiles))),
+            ("plx000", None),
+            ("pl00", None),
+            ("pl00000", None),
+            ("wp_0010", None),
+            ("readme", None),
+        ];
+
+        for (file_stem, expected) in cases {
+            assert_eq!(classify_data_file(file_stem), expected, "{}", file_stem);
+        }
+    }
+
+    #[test]
+    fn numeric_ids_are_four_hex_digits() {
+        let cases = [
+            ("0000", true),
+            ("000d", true),
+            ("00a0_2", true),
+            ("0010_", true),
+            ("x000", false),
+            ("00", false),
+            ("00000", false),
+            ("0000-2", false),
+            ("", false),
+        ];
+
+        for (identifier, expected) in cases {
+            assert_eq!(has_numeric_id(identifier), expected, "{}", identifier);
+        }
+    }
+
+    #[test]
+    fn component_root_contains_every_file() {
+        let files = [PathBuf::from("Mod/v1.2/data/pl/pl0000.dtt"), PathBuf::from("Mod/v1.2/data/pl/pl0010.dtt")];
+        assert_eq!(find_component_root(&files, Path::new("Mod")), PathBuf::from("Mod/v1.2/data/pl"));
+
+        let files = [PathBuf::from("Mod/data/pl/pl0000.dtt"), PathBuf::from("Mod/Optional/pl0010.dtt")];
+        assert_eq!(find_component_root(&files, Path::new("Mod")), PathBuf::from("Mod"));
+
+        assert_eq!(find_component_root(&[], Path::new("Mod")), PathBuf::from("Mod"));
+    }
+
+    #[test]
+    fn mirrored_game_folder_is_found_inside_the_search_folder() {
+        let file = Path::new("Mod/v1.2/data/pl/pl0000.dtt");
+        assert_eq!(mirrored_game_folder(file, ModType::PlayerModels, Path::new("Mod")), Some(PathBuf::from("Mod/v1.2/data/pl")));
+        assert_eq!(mirrored_game_folder(file, ModType::PlayerModels, Path::new("Mod/v1.2/data/pl")), Some(PathBuf::from("Mod/v1.2/data/pl")));
+        assert_eq!(mirrored_game_folder(file, ModType::WeaponModels, Path::new("Mod")), None);
+
+        let texture = Path::new("Mod/SK_Res/Inject/Textures/ui/a.dds");
+        assert_eq!(mirrored_game_folder(texture, ModType::Textures, Path::new("Mod")), Some(PathBuf::from("Mod/SK_Res/Inject/Textures")));
+
+        // Folders outside the mod don't count, even if they are named like the game's
+        let file = Path::new("data/pl/Mod/pl0000.dtt");
+        assert_eq!(mirrored_game_folder(file, ModType::PlayerModels, Path::new("data/pl/Mod")), None);
+    }
+
+    #[test]
+    fn game_layout_is_stripped_for_each_file() {
+        let component = detected_component(ModType::PlayerModels, &["Mod/data/pl/pl0000.dtt", "Mod/Optional/pl0010.dtt"]);
+        assert_eq!(component_file_paths(&component), vec![
+            (PathBuf::from("Mod/data/pl/pl0000.dtt"), PathBuf::from("pl0000.dtt")),
+            (PathBuf::from("Mod/Optional/pl0010.dtt"), PathBuf::from("pl0010.dtt")),
+        ]);
+
+        let component = detected_component(ModType::Textures, &["Mod/textures/a.dds", "Mod/textures/sub/b.dds"]);
+        assert_eq!(component_file_paths(&component), vec![
+            (PathBuf::from("Mod/textures/a.dds"), PathBuf::from("a.dds")),
+            (PathBuf::from("Mod/textures/sub/b.dds"), PathBuf::from("sub/b.dds")),
+        ]);
+    }
+}
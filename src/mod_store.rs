@@ -0,0 +1,30 @@
+use std::fs::{copy, create_dir_all};
+
+use std::io;
+
+use std::path::{Path, PathBuf};
+
+use crate::ata_folders::ata_folders;
+
+
+
+// Folder where ATA keeps a copy of every installed mod, enabled or not
+// The game folder only gets copies of these files, so it can always be rebuilt from here
+pub fn mod_store_folder() -> PathBuf {
+    ata_folders().data.join("mods")
+}
+
+// Folder of a single mod in the store, its files keep their path relative to the game folder (mods/3/data/pl/pl0000.dtt)
+pub fn stored_mod_folder(mod_id: u32) -> PathBuf {
+    mod_store_folder().join(mod_id.to_string())
+}
+
+// Copies a file, creating the folders that contain its new location
+pub fn copy_file(source_path: &Path, target_path: &Path) -> Result<(), io::Error> {
+    if let Some(target_parent) = target_path.parent() {
+        create_dir_all(target_parent)?;
+    }
+    copy(source_path, target_path)?;
+
+    Ok(())
+}
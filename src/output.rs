@@ -0,0 +1,122 @@
+use std::error::Error;
+
+use std::io::{self, stdout, Write};
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use clap::ValueEnum;
+
+use serde::Serialize;
+
+use serde_json::json;
+
+use crate::ata_folders::FoldersError;
+
+use crate::cli::CommandError;
+
+use crate::data_saving::ConfigError;
+
+use crate::features::ModManagingError;
+
+use crate::installation_utilities_and_methods::InstallationError;
+
+
+
+// How commands print their results
+#[derive(ValueEnum, Clone, Copy, PartialEq, Default)]
+pub enum OutputFormat {
+    // Made to be read by people
+    #[default]
+    Text,
+    // Made to be read by scripts, a single JSON value on stdout for each command
+    Json,
+}
+
+static JSON_OUTPUT: AtomicBool = AtomicBool::new(false);
+
+pub fn set_output_format(output_format: OutputFormat) {
+    JSON_OUTPUT.store(output_format == OutputFormat::Json, Ordering::Relaxed);
+}
+
+pub fn json_output() -> bool {
+    JSON_OUTPUT.load(Ordering::Relaxed)
+}
+
+// Messages about what ATA is doing while it does it
+// They go to stderr when stdout is used for JSON, so they don't break it
+pub fn print_notice(message: &str) {
+    if json_output() {
+        eprintln!("{}", message);
+    } else {
+        println!("{}", message);
+    }
+}
+
+// Written without println!, which panics when stdout gets closed early (piping into head)
+pub fn print_json(value: &impl Serialize) -> Result<(), Box<dyn Error>> {
+    writeln!(stdout(), "{}", serde_json::to_string_pretty(value)?)?;
+    Ok(())
+}
+
+// Prints the error that stopped a command, as {"error": {"code": ..., "message": ...}} when the output is JSON
+pub fn print_error(error: &(dyn Error + 'static)) {
+    if json_output() {
+        // Nothing else can be done if stdout is closed
+        let _ = writeln!(stdout(), "{:#}", json!({ "error": { "code": error_code(error), "message": error.to_string() } }));
+    } else {
+        eprintln!("{}", error);
+    }
+}
+
+// Name of the kind of error, scripts can rely on it not changing between versions (unlike the messages)
+pub fn error_code(error: &(dyn Error + 'static)) -> &'static str {
+    if let Some(error) = error.downcast_ref::<ModManagingError>() {
+        error.code()
+    } else if let Some(error) = error.downcast_ref::<CommandError>() {
+        error.code()
+    } else if let Some(error) = error.downcast_ref::<InstallationError>() {
+        error.code()
+    } else if let Some(error) = error.downcast_ref::<ConfigError>() {
+        error.code()
+    } else if let Some(error) = error.downcast_ref::<FoldersError>() {
+        error.code()
+    } else if is_archive_error(error) {
+        "archive_error"
+    } else if error.is::<clap::Error>() {
+        "usage_error"
+    } else if error.is::<serde_json::Error>() {
+        "json_error"
+    } else if error.is::<io::Error>() || error.is::<walkdir::Error>() {
+        "io_error"
+    } else {
+        "other_error"
+    }
+}
+
+// Errors of the decompressors, the RAR one only exists when ATA is built with RAR support
+fn is_archive_error(error: &(dyn Error + 'static)) -> bool {
+    #[cfg(feature = "rar")]
+    if error.is::<unrar::error::UnrarError>() {
+        return true;
+    }
+
+    error.is::<zip::result::ZipError>() || error.is::<sevenz_rust2::Error>()
+}
+
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decompressor_errors_are_archive_errors() {
+        assert_eq!(error_code(&zip::result::ZipError::FileNotFound), "archive_error");
+
+        #[cfg(feature = "rar")]
+        {
+            let rar_error = unrar::error::UnrarError { code: unrar::error::Code::BadData, when: unrar::error::When::Read };
+            assert_eq!(error_code(&rar_error), "archive_error");
+        }
+    }
+}
@@ -1,11 +1,11 @@
 use std::fs::read_dir;
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 
 
 // CHECK IF GIVEN PATH CONTAINS GAME FILES
-pub fn check_path(current_path: &PathBuf) -> Result<bool, std::io::Error> {
+pub fn check_path(current_path: &Path) -> Result<bool, std::io::Error> {
     let is_gamepath = read_dir(current_path)?       
         .filter_map(|res| {     // For each entry return Some(is-exe) or None and warn the user that an entry couldn't be read
             match res {
@@ -24,7 +24,7 @@ pub fn check_path(current_path: &PathBuf) -> Result<bool, std::io::Error> {
 
 
 // CHECKING IF REQUIRED MODDING FILES ARE ALREADY PRESENT
-pub fn check_for_required_modding_files(game_path: &PathBuf) -> Vec<PathBuf> {
+pub fn check_for_required_modding_files(game_path: &Path) -> Vec<PathBuf> {
     let required_files = [
         "NieRAutomata.exe",
         "d3d11.dll",
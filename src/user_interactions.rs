@@ -1,12 +1,20 @@
 use std::error::Error;
 
-use std::env::var;
-
 use std::io::{stdin, stdout, Write};
 
 use std::process::{Command, ExitStatus};
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use crate::ata_folders::ata_folders;
+
+use crate::data_saving::{format_timestamp, ModType};
+
+use crate::features::mod_list;
+
+use crate::mod_conflicts::{ConflictChoice, FileConflict};
+
+use crate::mod_detection::DetectionReport;
 
 
 
@@ -41,8 +49,7 @@ pub fn missing_files_warning(missing_files: Vec<PathBuf>) -> Result<bool, std::i
 
 // IF THE USER WANTS TO, INSTALL THE FILES
 pub fn run_auto_install_script() -> Result<ExitStatus, Box<dyn Error>> {
-    let home_dir = var("HOME").unwrap_or(String::from("/home/2B/"));
-    let script_path = PathBuf::from(format!("{}/.local/share/ATA/install-prerequisites.sh", home_dir));
+    let script_path = ata_folders().data.join("install-prerequisites.sh");
 
     let status = Command::new("xdg-terminal-exec")
         .arg("-e")
@@ -60,9 +67,14 @@ pub fn ask_user_action() -> Result<String, std::io::Error> {
     // Asking what the user wants to do
     println!(
         "What do you want to do?\n
-            \t1 - Install a mod (you have to provide a zip folder of the mod)
-            \t2 - Uninstall a mod (you have to type the name of the mod)
+            \t1 - Install a mod (you have to provide the archive, folder or file of the mod)
+            \t2 - Uninstall a mod (you have to type the name or ID of the mod)
             \t3 - List all mods
+            \t4 - Enable a mod (puts its files back in the game folder)
+            \t5 - Disable a mod (removes its files from the game folder without uninstalling it)
+            \t6 - Show the details of a mod (files, replaced game files, conflicts)
+            \t7 - Change the load order (mods later in the order win the files they share with earlier ones)
+            \t8 - Repair the game folder (puts back the files of the enabled mods from ATA's mod store)
             \t0 - Close ATA"
     );
     print!("\nInsert a number: ");
@@ -75,12 +87,218 @@ pub fn ask_user_action() -> Result<String, std::io::Error> {
 }
 
 pub fn ask_for_mod_folder() -> Result<PathBuf, std::io::Error> {
-    println!("To install a mod type the path to the compressed folder of a mod you downloaded\n\
-        IT HAS TO BE A COMPRESSED FOLDER (.zip, .7z, .rar)");
+    println!("To install a mod type the path to the mod you downloaded\n\
+        IT CAN BE A COMPRESSED FOLDER (.zip, .7z, .rar), AN EXTRACTED FOLDER OR A SINGLE MOD FILE (.dds, .dat, .dtt, .usm)");
     print!("Insert path >> ");
     stdout().flush()?;
 
     let mut answer = String::new();
     stdin().read_line(&mut answer)?;
     Ok(PathBuf::from(answer.trim()))
-}
\ No newline at end of file
+}
+
+// SHOWING WHAT WAS FOUND IN THE MOD BEFORE INSTALLING IT
+pub fn show_detection_report(report: &DetectionReport, mod_folder_path: &Path) {
+    println!("\nThe mod contains:");
+    for (index, component) in report.components.iter().enumerate() {
+        println!("\t{} - {:?} ({:.0}% sure), {} file(s) in {:?}",
+            index + 1,
+            component.mod_type,
+            component.confidence * 100.0,
+            component.files.len(),
+            component.root.strip_prefix(mod_folder_path).unwrap_or(&component.root));
+
+        for file in &component.files {
+            println!("\t\t{:?}", file.strip_prefix(mod_folder_path).unwrap_or(file));
+        }
+    }
+
+    if !report.unclassified_files.is_empty() {
+        println!("\nThese files aren't part of any known type of mod, they're only installed if you say what they are:");
+        for file in &report.unclassified_files {
+            println!("\t\t{:?}", file.strip_prefix(mod_folder_path).unwrap_or(file));
+        }
+    }
+    println!();
+}
+
+// LETTING THE USER FIX THE TYPES THAT WERE DETECTED
+pub fn ask_mod_type_overrides(report: &mut DetectionReport) -> Result<(), std::io::Error> {
+    let mut types_shown = false;
+
+    if !report.components.is_empty() {
+        print!("Are the detected types correct? [Y/n] ");
+        stdout().flush()?;
+
+        let mut answer = String::new();
+        stdin().read_line(&mut answer)?;
+        let answer = answer.trim();
+        if !answer.is_empty() && !answer.eq_ignore_ascii_case("y") {
+            show_mod_types();
+            types_shown = true;
+
+            for (index, component) in report.components.iter_mut().enumerate() {
+                if let Some(new_type) = ask_mod_type(&format!("Type of part {} (leave empty to keep {:?}): ", index + 1, component.mod_type))? {
+                    component.mod_type = new_type;
+                }
+            }
+        }
+    }
+
+    // Files ATA didn't recognize are only installed if the user says what they are
+    if !report.unclassified_files.is_empty() {
+        if !types_shown {
+            show_mod_types();
+        }
+
+        if let Some(mod_type) = ask_mod_type("Type of the files that weren't recognized (leave empty to not install them): ")? {
+            report.classify_unclassified_files(mod_type);
+        }
+    }
+
+    Ok(())
+}
+
+fn show_mod_types() {
+    println!("Available types:");
+    for (index, mod_type) in ModType::ALL.iter().enumerate() {
+        println!("\t{} - {:?}", index + 1, mod_type);
+    }
+}
+
+// Asks until the answer is one of the available types, or nothing
+fn ask_mod_type(question: &str) -> Result<Option<ModType>, std::io::Error> {
+    loop {
+        print!("{}", question);
+        stdout().flush()?;
+
+        let mut answer = String::new();
+        stdin().read_line(&mut answer)?;
+        let answer = answer.trim();
+        if answer.is_empty() {
+            return Ok(None);
+        }
+
+        match answer.parse::<usize>().ok().and_then(|number| ModType::ALL.get(number.wrapping_sub(1))) {
+            Some(new_type) => return Ok(Some(*new_type)),
+            None => println!("\"{}\" is not one of the available types", answer),
+        }
+    }
+}
+
+// ASKING WHICH MOD TO UNINSTALL, ENABLE, DISABLE OR SHOW
+pub fn ask_for_installed_mod(action: &str) -> Result<String, std::io::Error> {
+    println!("To {} a mod type its name or ID", action);
+    print!("Insert name or ID >> ");
+    stdout().flush()?;
+
+    let mut answer = String::new();
+    stdin().read_line(&mut answer)?;
+    Ok(answer.trim().to_string())
+}
+
+// ASKING WHERE TO MOVE A MOD IN THE LOAD ORDER
+// Returns the name or ID of the mod it goes before, nothing means at the end
+pub fn ask_order_place() -> Result<String, std::io::Error> {
+    println!("Type the name or ID of the mod it goes before (nothing to put it last, where it wins every file it shares)");
+    print!("Insert name or ID >> ");
+    stdout().flush()?;
+
+    let mut answer = String::new();
+    stdin().read_line(&mut answer)?;
+    Ok(answer.trim().to_string())
+}
+
+// ASKING HOW TO RECOVER FROM A DATA FILE THAT CAN'T BE READ
+pub fn ask_recovery_action(available_backups: usize) -> Result<String, std::io::Error> {
+    println!(
+        "How do you want to recover?\n
+            \t1 - Restore a copy of the data file ({} available)
+            \t2 - Rebuild the list of mods from ATA's mod store and the game folder (mods will have to be renamed)
+            \t3 - Start with no mods (the files of the installed mods will stay in the game folder)
+            \t0 - Close ATA (to fix the data file by hand)",
+        available_backups
+    );
+    print!("\nInsert a number: ");
+    stdout().flush()?;
+
+    let mut answer = String::new();
+    stdin().read_line(&mut answer)?;
+    Ok(answer.trim().to_string())
+}
+
+// ASKING WHICH COPY OF THE DATA FILE TO RESTORE (NEWEST FIRST)
+pub fn ask_config_backup_to_restore(backups: &[(PathBuf, u64)]) -> Result<Option<usize>, std::io::Error> {
+    if backups.is_empty() {
+        println!("There are no copies of the data file to restore");
+        return Ok(None);
+    }
+
+    for (backup_index, (backup_path, backup_time)) in backups.iter().enumerate() {
+        println!("\t{} - {:?} (saved {})", backup_index + 1, backup_path, format_timestamp(*backup_time));
+    }
+    print!("\nInsert a number (nothing to go back): ");
+    stdout().flush()?;
+
+    let mut answer = String::new();
+    stdin().read_line(&mut answer)?;
+    Ok(answer
+        .trim()
+        .parse::<usize>()
+        .ok()
+        .filter(|number| (1..=backups.len()).contains(number))
+        .map(|number| number - 1))
+}
+
+// WARNING THE USER BEFORE REMOVING A FILE THAT CHANGED AFTER BEING INSTALLED
+pub fn ask_remove_modified_file(file_path: &Path) -> Result<bool, std::io::Error> {
+    println!("{:?} was modified after the mod was installed (by you, another program or another mod)", file_path);
+    print!("Remove it anyway? [y/N] ");
+    stdout().flush()?;
+
+    let mut answer = String::new();
+    stdin().read_line(&mut answer)?;
+    Ok(answer.trim().eq_ignore_ascii_case("y"))
+}
+
+// ASKING WHICH COPY OF EACH FILE INSTALLED MODS HAVE TOO GOES IN THE GAME FOLDER
+// An uppercase answer is used for all the remaining files, skipping stops at the first file
+pub fn ask_conflict_choices(conflicts: &[FileConflict]) -> Result<Vec<ConflictChoice>, std::io::Error> {
+    if conflicts.is_empty() {
+        return Ok(vec![]);
+    }
+    println!("{} files of the mod are also in installed mods", conflicts.len());
+
+    let mut choices: Vec<ConflictChoice> = vec![];
+    let mut choice_for_all: Option<ConflictChoice> = None;
+    for conflict in conflicts {
+        println!("{:?} is also in {}", conflict.path, mod_list(&conflict.other_mods));
+        if let Some(choice) = choice_for_all {
+            choices.push(choice);
+            continue;
+        }
+
+        let choice = loop {
+            print!("[o]verwrite it, [k]eep the installed one or [s]kip installing the mod? (O or K for all the files) ");
+            stdout().flush()?;
+
+            let mut answer = String::new();
+            stdin().read_line(&mut answer)?;
+            match answer.trim() {
+                "o" => break ConflictChoice::Overwrite,
+                "k" => break ConflictChoice::Keep,
+                "s" | "S" => break ConflictChoice::Skip,
+                "O" => { choice_for_all = Some(ConflictChoice::Overwrite); break ConflictChoice::Overwrite; }
+                "K" => { choice_for_all = Some(ConflictChoice::Keep); break ConflictChoice::Keep; }
+                answer => println!("\"{}\" is not a valid answer (input either o, k, s, O or K)", answer),
+            }
+        };
+
+        choices.push(choice);
+        if choice == ConflictChoice::Skip {
+            break;
+        }
+    }
+
+    Ok(choices)
+}
@@ -0,0 +1,59 @@
+use std::fs::{copy, create_dir_all, remove_file};
+
+use std::io;
+
+use std::path::{Path, PathBuf};
+
+use crate::ata_folders::ata_folders;
+
+
+
+// Folder where the original game files replaced by mods are kept, each one at its path relative to the game folder
+pub fn backups_folder() -> PathBuf {
+    ata_folders().data.join("backups")
+}
+
+// Where the original of a game file is kept (data/pl/pl0000.dtt -> backups/data/pl/pl0000.dtt)
+pub fn backup_path(game_relative_path: &Path) -> PathBuf {
+    backups_folder().join(game_relative_path)
+}
+
+// Makes sure the original of a game file is in the backups before a mod overwrites it
+// Returns whether there's a backup of the file (made now or by an earlier installation), false if the game doesn't have that file
+pub fn back_up_vanilla_file(game_path: &Path, game_relative_path: &Path) -> Result<bool, io::Error> {
+    let backup_file = backup_path(game_relative_path);
+    if backup_file.is_file() {
+        return Ok(true);
+    }
+
+    let game_file = game_path.join(game_relative_path);
+    if !game_file.is_file() {
+        return Ok(false);
+    }
+
+    if let Some(backup_parent) = backup_file.parent() {
+        create_dir_all(backup_parent)?;
+    }
+    copy(&game_file, &backup_file)?;
+
+    Ok(true)
+}
+
+// Puts the original file back in the game folder and removes it from the backups
+// Returns whether it was restored, it isn't if there's no backup or if something is still in its place (a modified file the user kept)
+pub fn restore_vanilla_file(game_path: &Path, game_relative_path: &Path) -> Result<bool, io::Error> {
+    let backup_file = backup_path(game_relative_path);
+    let game_file = game_path.join(game_relative_path);
+    if !backup_file.is_file() || game_file.exists() {
+        return Ok(false);
+    }
+
+    if let Some(game_parent) = game_file.parent() {
+        create_dir_all(game_parent)?;
+    }
+    // Backups and game can be on different drives, so the file is copied instead of moved
+    copy(&backup_file, &game_file)?;
+    remove_file(&backup_file)?;
+
+    Ok(true)
+}